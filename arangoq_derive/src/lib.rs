@@ -1,5 +1,5 @@
 #![forbid(unsafe_code)]
-#![recursion_limit = "256"]
+#![recursion_limit = "512"]
 
 extern crate proc_macro;
 
@@ -7,116 +7,315 @@ use proc_macro::TokenStream;
 use quote::*;
 use syn::{export::*, *};
 
-// TODO remove .unwrap calls
 // TODO make limiting consistent
 
 const DEFAULT_LIMIT: usize = 100;
 
-#[proc_macro_derive(ArangoBuilder)]
+/// The operators of the generated condition methods, by method name suffix.
+const COMPARISONS: [(&str, &str); 8] = [
+    ("eq", "=="),
+    ("ne", "!="),
+    ("gt", ">"),
+    ("lt", "<"),
+    ("ge", ">="),
+    ("le", "<="),
+    ("in", "IN"),
+    ("not_in", "NOT IN"),
+];
+
+#[proc_macro_derive(ArangoBuilder, attributes(arango))]
 pub fn arango_builder(item: TokenStream) -> TokenStream {
-    _arango_builder(parse_macro_input![item as ItemStruct]).into()
+    _arango_builder(parse_macro_input![item as ItemStruct])
+        .unwrap_or_else(|error| error.to_compile_error())
+        .into()
 }
 
-fn _arango_builder(struct_definition: ItemStruct) -> TokenStream2 {
+fn _arango_builder(struct_definition: ItemStruct) -> Result<TokenStream2> {
     let struct_name = &struct_definition.ident;
+    let generics = &struct_definition.generics;
+    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+    let struct_type = quote![#struct_name #ty_generics];
+    let (struct_generics, struct_bounds) = method_generics(generics, &struct_type);
     let builder_name_precursor = || format!("{}ArangoBuilder", struct_name);
     let builder_name = Ident::new(&builder_name_precursor(), Span::call_site());
     let builder_factory_name =
         Ident::new(&format!("{}ArangoBuilderFactory", struct_name), Span::call_site());
-    let default_limit = quote![serde_json::to_value(&#DEFAULT_LIMIT).unwrap()]; // safe to unwrap
+    let default_limit = quote![serde_json::Value::from(#DEFAULT_LIMIT)];
+
+    // Fields as serde stores them, skipped fields get neither filters nor updates
+    let named_fields = match &struct_definition.fields {
+        Fields::Named(fields) => &fields.named,
+        Fields::Unnamed(fields) => {
+            return Err(Error::new_spanned(
+                fields,
+                "ArangoBuilder can only be derived for structs with named fields",
+            ))
+        }
+        Fields::Unit => {
+            return Err(Error::new_spanned(
+                struct_name,
+                "ArangoBuilder can only be derived for structs with named fields",
+            ))
+        }
+    };
+    let rename_all = serde_rename_all(&struct_definition.attrs);
+    let fields = named_fields
+        .iter()
+        .map(|field| SerdeField::new(field, rename_all.as_deref()))
+        .collect::<Result<Vec<Option<SerdeField>>>>()?
+        .into_iter()
+        .flatten()
+        .collect::<Vec<SerdeField>>();
+    let attribute_fields =
+        fields.iter().filter(|field| !field.flatten).collect::<Vec<&SerdeField>>();
 
     // Struct specific methods for field based filtering
-    let condition_qs = struct_definition
-        .fields
+    let condition_qs = attribute_fields
         .iter()
-        .flat_map(|field| {
-            [
-                ("eq", "=="),
-                ("ne", "!="),
-                ("gt", ">"),
-                ("lt", "<"),
-                ("ge", ">="),
-                ("le", "<="),
-                ("in", "IN"),
-                ("not_in", "NOT IN"),
-            ]
-            .iter()
-            .map(move |(op_name, op)| {
-                let id = field_id(&field);
+        .flat_map(|serde_field| {
+            let field = serde_field.field;
+            COMPARISONS.iter().map(move |(op_name, op)| {
                 let ty = &field.ty;
-                let call = format!("item.{}", id);
-                let fn_name = Ident::new(&format!("{}_{}", id, op_name), Span::call_site());
+                let (method_generics, method_bounds) = method_generics(generics, ty);
+                let call = serde_field.access();
+                let fn_name = serde_field.method_name(op_name);
                 let bn = Ident::new(&builder_name_precursor(), Span::call_site());
 
                 let doc_comment = format!(
                     "/// Test doc comment for {}.
                     /// with {}
-                    /// call {}",
+                    /// call item{}",
                     fn_name, bn, call
                 );
                 match op_name {
-                    &"in" | &"not_in" =>
-                        quote![
-                            #[doc = #doc_comment]
-                            pub fn #fn_name(self, values: &[#ty]) -> #bn<Conditional> {
-                                let mut new_bind_vars = self.bind_vars;
-                                let bind_var_name = format!("filterVar{}", new_bind_vars.len());
-
-                                new_bind_vars
-                                    .insert(
-                                        bind_var_name.clone(),
-                                        serde_json::to_value(&values).unwrap()
-                                    );
-
-                                let mut new_raw_query = self.raw_query;
-                                new_raw_query.push(format!("{} {} @{}", #call, #op, bind_var_name));
-
-                                #bn {
-                                    query_type: self.query_type,
-                                    tag: Conditional,
-                                    bind_vars: new_bind_vars,
-                                    raw_query: new_raw_query,
-                                }
-                            }
-                        ],
-                    _ =>
-                        quote![
-                            #[allow(clippy::ptr_arg)]
-                            pub fn #fn_name(self, value: &#ty) -> #bn<Conditional> {
-                                let mut new_bind_vars = self.bind_vars;
-                                let bind_var_name = format!("filterVar{}", new_bind_vars.len());
-
-                                new_bind_vars
-                                    .insert(bind_var_name.clone(), serde_json::to_value(&value).unwrap());
-
-                                let mut new_raw_query = self.raw_query;
-                                new_raw_query.push(format!("{} {} @{}", #call, #op, bind_var_name));
-
-                                #bn {
-                                    query_type: self.query_type,
-                                    tag: Conditional,
-                                    bind_vars: new_bind_vars,
-                                    raw_query: new_raw_query,
-                                }
-                            }
-                        ]
+                    &"in" | &"not_in" => quote![
+                        #[doc = #doc_comment]
+                        pub fn #fn_name #method_generics(mut self, values: &[#ty]) -> #bn<Tag::Next>
+                        #method_bounds
+                        {
+                            let bind_var_name = self.filter_var(&values);
+                            let condition =
+                                format!("{}{} {} @{}", self.document, #call, #op, bind_var_name);
+                            self.condition(condition)
+                        }
+                    ],
+                    _ => quote![
+                        #[allow(clippy::ptr_arg)]
+                        pub fn #fn_name #method_generics(mut self, value: &#ty) -> #bn<Tag::Next>
+                        #method_bounds
+                        {
+                            let bind_var_name = self.filter_var(&value);
+                            let condition =
+                                format!("{}{} {} @{}", self.document, #call, #op, bind_var_name);
+                            self.condition(condition)
+                        }
+                    ],
                 }
             })
         })
         .collect::<Vec<TokenStream2>>();
 
-    // Struct specific methods for field based filtering
-    let condition_qs_str =
-        [
-            ("eq", "=="),
-            ("ne", "!="),
-            ("gt", ">"),
-            ("lt", "<"),
-            ("ge", ">="),
-            ("le", "<="),
-            ("in", "IN"),
-            ("not_in", "NOT IN"),
-        ]
+    // Struct specific methods for filtering on array fields
+    let array_condition_qs = attribute_fields
+        .iter()
+        .filter_map(|field| array_element(&field.field.ty).map(|elem_ty| (field, elem_ty)))
+        .flat_map(|(serde_field, elem_ty)| {
+            [
+                ("any_eq", "ANY =="),
+                ("any_in", "ANY IN"),
+                ("all_in", "ALL IN"),
+                ("none_eq", "NONE =="),
+                ("none_in", "NONE IN"),
+                ("length_eq", "=="),
+                ("length_ne", "!="),
+                ("length_gt", ">"),
+                ("length_lt", "<"),
+                ("length_ge", ">="),
+                ("length_le", "<="),
+            ]
+            .iter()
+            .map(move |(op_name, op)| {
+                let (method_generics, method_bounds) = method_generics(generics, elem_ty);
+                let call = serde_field.access();
+                let fn_name = serde_field.method_name(op_name);
+                let bn = Ident::new(&builder_name_precursor(), Span::call_site());
+
+                match op_name {
+                    &"any_in" | &"all_in" | &"none_in" => quote![
+                        pub fn #fn_name #method_generics(mut self, values: &[#elem_ty]) -> #bn<Tag::Next>
+                        #method_bounds
+                        {
+                            let bind_var_name = self.filter_var(&values);
+                            let condition =
+                                format!("{}{} {} @{}", self.document, #call, #op, bind_var_name);
+                            self.condition(condition)
+                        }
+                    ],
+                    &"any_eq" | &"none_eq" => quote![
+                        #[allow(clippy::ptr_arg)]
+                        pub fn #fn_name #method_generics(mut self, value: &#elem_ty) -> #bn<Tag::Next>
+                        #method_bounds
+                        {
+                            let bind_var_name = self.filter_var(&value);
+                            let condition =
+                                format!("{}{} {} @{}", self.document, #call, #op, bind_var_name);
+                            self.condition(condition)
+                        }
+                    ],
+                    _ => quote![
+                        pub fn #fn_name(mut self, length: usize) -> #bn<Tag::Next> {
+                            let bind_var_name = self.filter_var(&length);
+                            let condition = format!(
+                                "LENGTH({}{}) {} @{}",
+                                self.document, #call, #op, bind_var_name
+                            );
+                            self.condition(condition)
+                        }
+                    ],
+                }
+            })
+        })
+        .collect::<Vec<TokenStream2>>();
+
+    // Struct specific methods for matching string fields
+    let string_condition_qs = attribute_fields
+        .iter()
+        .filter(|field| is_string(&field.field.ty))
+        .flat_map(|serde_field| {
+            [
+                ("like", "{0} LIKE {1}"),
+                ("not_like", "{0} NOT LIKE {1}"),
+                ("regex", "{0} =~ {1}"),
+                ("starts_with", "STARTS_WITH({0}, {1})"),
+                ("contains", "CONTAINS({0}, {1})"),
+                ("eq_ci", "LOWER({0}) == LOWER({1})"),
+                ("like_ci", "LIKE({0}, {1}, true)"),
+                ("regex_ci", "REGEX_TEST({0}, {1}, true)"),
+                ("starts_with_ci", "STARTS_WITH(LOWER({0}), LOWER({1}))"),
+                ("contains_ci", "CONTAINS(LOWER({0}), LOWER({1}))"),
+            ]
+            .iter()
+            .map(move |(op_name, template)| {
+                let call = serde_field.access();
+                let fn_name = serde_field.method_name(op_name);
+                let bn = Ident::new(&builder_name_precursor(), Span::call_site());
+
+                quote![
+                    pub fn #fn_name(mut self, value: &str) -> #bn<Tag::Next> {
+                        let bind_var_name = self.filter_var(value);
+                        let condition = format!(
+                            #template,
+                            format!("{}{}", self.document, #call),
+                            format!("@{}", bind_var_name)
+                        );
+                        self.condition(condition)
+                    }
+                ]
+            })
+        })
+        .collect::<Vec<TokenStream2>>();
+
+    // Struct specific methods for checking the presence of attributes
+    let existence_qs = attribute_fields
+        .iter()
+        .flat_map(|serde_field| {
+            // the templates are format strings of the document, the names are escaped for them
+            let access = format_literal(&serde_field.access());
+            let attribute = format_literal(&aql_string(&serde_field.attribute));
+            let mut checks = vec![
+                ("exists", format!("HAS({{0}}, {})", attribute)),
+                ("missing", format!("NOT HAS({{0}}, {})", attribute)),
+            ];
+            if is_option(&serde_field.field.ty) {
+                checks.push((
+                    "is_null",
+                    format!("(HAS({{0}}, {}) AND {{0}}{} == null)", attribute, access),
+                ));
+                checks.push(("is_not_null", format!("{{0}}{} != null", access)));
+            }
+            checks.into_iter().map(move |(op_name, template)| {
+                let fn_name = serde_field.method_name(op_name);
+                let bn = Ident::new(&builder_name_precursor(), Span::call_site());
+
+                quote![
+                    pub fn #fn_name(self) -> #bn<Tag::Next> {
+                        let condition = format!(#template, self.document);
+                        self.condition(condition)
+                    }
+                ]
+            })
+        })
+        .collect::<Vec<TokenStream2>>();
+
+    // Struct specific methods for filtering on fields of nested documents
+    let nested_condition_qs = fields
+        .iter()
+        .filter_map(|serde_field| {
+            serde_field.nested.as_ref().map(|nested_bn| (serde_field, nested_bn))
+        })
+        .map(|(serde_field, nested_bn)| {
+            let call = serde_field.access();
+            let fn_name = serde_field.method_name("where");
+            let bn = Ident::new(&builder_name_precursor(), Span::call_site());
+
+            quote![
+                /// Parenthesized group of conditions on the fields of the nested document
+                /// joined by `AND`: `.address_where(|a| a.city_eq(&"Liverpool"))`
+                pub fn #fn_name<F>(self, group: F) -> #bn<Tag::Next>
+                where
+                    F: FnOnce(#nested_bn<AllOf>) -> #nested_bn<AllOfConditional>,
+                {
+                    let document = format!("{}{}", self.document, #call);
+                    let grouped = group(#nested_bn::nested(document, self.bind_vars, AllOf));
+                    let (bind_vars, raw_query) = grouped.into_parts();
+
+                    #bn {
+                        query_type: self.query_type,
+                        tag: self.tag,
+                        bind_vars,
+                        raw_query: self.raw_query,
+                        return_clause: self.return_clause,
+                        collect_vars: self.collect_vars,
+                        update_vars: self.update_vars,
+                        update_options: self.update_options,
+                        document: self.document,
+                    }
+                    .condition(format!("({})", raw_query.join(" ")))
+                }
+            ]
+        })
+        .collect::<Vec<TokenStream2>>();
+
+    // Flat methods for filtering on fields of nested documents, e.g. `address_city_eq`,
+    // generated by the macro the derive of the nested struct defines
+    let nested_flat_qs = fields
+        .iter()
+        .filter(|serde_field| serde_field.nested.is_some())
+        .filter_map(|serde_field| {
+            let nested_fields =
+                nested_fields_macro_name(nested_struct_name(&serde_field.field.ty)?);
+            let prefix = Ident::new(&serde_field.name(), Span::call_site());
+            let access = serde_field.access();
+            Some(quote![#nested_fields! { #builder_name, #prefix, #access }])
+        })
+        .collect::<Vec<TokenStream2>>();
+
+    // The fields this struct offers to the flat methods of parents nesting it,
+    // not for generic structs whose field types depend on the parameters
+    let nested_fields_macro = nested_fields_macro_name(struct_name);
+    let nested_fields = attribute_fields
+        .iter()
+        .filter(|_| generics.params.is_empty())
+        .map(|serde_field| {
+            let ident = &serde_field.ident;
+            let ty = &serde_field.field.ty;
+            let access = serde_field.access();
+            quote![#ident: #ty => #access]
+        })
+        .collect::<Vec<TokenStream2>>();
+
+    // Methods for filtering on attribute paths, the attribute names are bound as `item[@attr]`
+    let condition_qs_str = COMPARISONS
         .iter()
         .map(move |(op_name, op)| {
             let bn = Ident::new(&builder_name_precursor(), Span::call_site());
@@ -131,119 +330,228 @@ fn _arango_builder(struct_definition: ItemStruct) -> TokenStream2 {
                 &"in" | &"not_in" =>
                     quote![
                         #[doc = #doc_comment]
-                        pub fn #fn_name<T>(self, prop_name: &str, values: &[T]) -> #bn<Conditional> where T: Serialize {
-                            let mut new_bind_vars = self.bind_vars;
-                            let bind_var_name = format!("filterVar{}", new_bind_vars.len());
-
-                            new_bind_vars
-                                .insert(
-                                    bind_var_name.clone(),
-                                    serde_json::to_value(&values).unwrap()
-                                );
-
-                            let mut new_raw_query = self.raw_query;
-                            new_raw_query.push(format!("item.{} {} @{}", prop_name, #op, bind_var_name));
-
-                            #bn {
-                                query_type: self.query_type,
-                                tag: Conditional,
-                                bind_vars: new_bind_vars,
-                                raw_query: new_raw_query,
-                            }
+                        pub fn #fn_name<T>(mut self, prop_name: &str, values: &[T]) -> #bn<Tag::Next> where T: Serialize {
+                            let attribute = self.attribute_path(prop_name);
+                            let bind_var_name = self.filter_var(&values);
+                            self.condition(format!("{} {} @{}", attribute, #op, bind_var_name))
                         }
                     ],
                 _ =>
                     quote![
                         #[allow(clippy::ptr_arg)]
-                        pub fn #fn_name<T>(self, prop_name: &str, value: &T) -> #bn<Conditional> where T: Serialize {
-                            let mut new_bind_vars = self.bind_vars;
-                            let bind_var_name = format!("filterVar{}", new_bind_vars.len());
-
-                            new_bind_vars
-                                .insert(bind_var_name.clone(), serde_json::to_value(&value).unwrap());
-
-                            let mut new_raw_query = self.raw_query;
-                            new_raw_query.push(format!("item.{} {} @{}", prop_name, #op, bind_var_name));
-
-                            #bn {
-                                query_type: self.query_type,
-                                tag: Conditional,
-                                bind_vars: new_bind_vars,
-                                raw_query: new_raw_query,
-                            }
+                        pub fn #fn_name<T>(mut self, prop_name: &str, value: &T) -> #bn<Tag::Next> where T: Serialize {
+                            let attribute = self.attribute_path(prop_name);
+                            let bind_var_name = self.filter_var(&value);
+                            self.condition(format!("{} {} @{}", attribute, #op, bind_var_name))
                         }
                     ]
             }
         })
     .collect::<Vec<TokenStream2>>();
 
-    // Struct specific methods for field based updating
-    let with_qs = struct_definition
-        .fields
-        .into_iter()
-        .map(|field| {
-            let id = field_id(&field);
-            let ty = &field.ty;
-            let call = format!("{}", id);
-            let fn_name = Ident::new(&format!("{}", id), Span::call_site());
+    // Struct specific enum to refer to fields in a typed manner
+    let field_enum_name = Ident::new(&format!("{}ArangoField", struct_name), Span::call_site());
+    let field_variants = attribute_fields
+        .iter()
+        .map(|field| Ident::new(&variant_name(&field.ident), Span::call_site()))
+        .collect::<Vec<Ident>>();
+    let field_enum_attributes = field_variants
+        .iter()
+        .zip(&attribute_fields)
+        .map(|(variant, field)| {
+            let attribute = &field.attribute;
+            quote![#field_enum_name::#variant => #attribute]
+        })
+        .collect::<Vec<TokenStream2>>();
+    let field_enum_accesses = field_variants
+        .iter()
+        .zip(&attribute_fields)
+        .map(|(variant, field)| {
+            let access = field.access();
+            quote![#field_enum_name::#variant => #access]
+        })
+        .collect::<Vec<TokenStream2>>();
+
+    // Struct specific methods for field based updating, accumulated into a single UPDATE
+    let with_qs = fields
+        .iter()
+        .map(|serde_field| {
+            let ty = &serde_field.field.ty;
+            let (method_generics, method_bounds) = method_generics(generics, ty);
+            let fn_name = &serde_field.ident;
             let bn = Ident::new(&builder_name_precursor(), Span::call_site());
 
+            // the attributes of flattened fields are merged into the document
+            let member = if serde_field.flatten {
+                quote![String::from(var)]
+            } else {
+                let key = aql_key(&serde_field.attribute);
+                quote![format!("{}: {}", #key, var)]
+            };
+
             quote![
                 #[allow(clippy::ptr_arg)]
-                pub fn #fn_name(self, value: &#ty) -> #bn<UpdateField> {
-                    let mut new_bind_vars = self.bind_vars;
-                    let bind_var_name = format!("withVar{}", new_bind_vars.len());
-                    new_bind_vars
-                        .insert(bind_var_name.clone(), serde_json::to_value(&value).unwrap());
+                pub fn #fn_name #method_generics(self, value: &#ty) -> #bn<Updating>
+                #method_bounds
+                {
+                    self.update_with(|var| #member, value)
+                }
+            ]
+        })
+        .collect::<Vec<TokenStream2>>();
 
-                    let mut new_raw_query = self.raw_query;
-                    new_raw_query.push(
-                        format!("UPDATE item WITH {{ {}: @{} }}", #call, bind_var_name)
-                    );
+    // Struct specific methods for updating fields with server side expressions
+    let expression_qs = attribute_fields
+        .iter()
+        .flat_map(|serde_field| {
+            let key = aql_key(&serde_field.attribute);
+            let access = serde_field.access();
+            let bn = Ident::new(&builder_name_precursor(), Span::call_site());
 
-                    #bn {
-                        query_type: self.query_type,
-                        tag: UpdateField,
-                        bind_vars: new_bind_vars,
-                        raw_query: new_raw_query,
+            let mut expressions = vec![];
+            if let Some(number_ty) = numeric_type(&serde_field.field.ty) {
+                for (op_name, op) in &[("increment", "+"), ("decrement", "-")] {
+                    let fn_name = serde_field.method_name(op_name);
+                    expressions.push(quote![
+                        pub fn #fn_name(self, by: &#number_ty) -> #bn<Updating> {
+                            self.update_with(|var| format!("{}: item{} {} {}", #key, #access, #op, var), by)
+                        }
+                    ]);
+                }
+            }
+            if let Some(elem_ty) = array_element(&serde_field.field.ty) {
+                let (method_generics, method_bounds) = method_generics(generics, elem_ty);
+                for (op_name, function, many) in &[
+                    ("push", "PUSH", false),
+                    ("append", "APPEND", true),
+                    ("remove", "REMOVE_VALUE", false),
+                    ("remove_all", "REMOVE_VALUES", true),
+                ] {
+                    let fn_name = serde_field.method_name(op_name);
+                    let value_ty = if *many { quote![[#elem_ty]] } else { quote![#elem_ty] };
+                    expressions.push(quote![
+                        #[allow(clippy::ptr_arg)]
+                        pub fn #fn_name #method_generics(self, value: &#value_ty) -> #bn<Updating>
+                        #method_bounds
+                        {
+                            self.update_with(
+                                |var| format!("{}: {}(item{}, {})", #key, #function, #access, var),
+                                value,
+                            )
+                        }
+                    ]);
+                }
+            }
+            expressions
+        })
+        .collect::<Vec<TokenStream2>>();
+
+    // the first aggregate or the count of the groups, or of all documents
+    let aggregatable_impls = [quote![Grouping], quote![CollectingAll]]
+        .iter()
+        .map(|tag| {
+            quote![
+                impl #builder_name<#tag> {
+                    /// Adds an `AGGREGATE` to the groups, returned as e.g. `{ "sum_age": 84 }`.
+                    pub fn aggregate(self, function: Aggregate, field: #field_enum_name) -> #builder_name<Aggregating> {
+                        let mut new_raw_query = self.raw_query;
+                        let mut new_collect_vars = self.collect_vars;
+    
+                        let aggregate = Self::aggregate_expression(&mut new_collect_vars, function, field);
+                        new_raw_query.push(format!("AGGREGATE {}", aggregate));
+    
+                        #builder_name {
+                            query_type: self.query_type,
+                            tag: Aggregating,
+                            bind_vars: self.bind_vars,
+                            raw_query: new_raw_query,
+                            return_clause: self.return_clause,
+                            collect_vars: new_collect_vars,
+                            update_vars: self.update_vars,
+                            update_options: self.update_options,
+                            document: self.document,
+                        }
+                    }
+    
+                    /// Counts the documents of the groups with `WITH COUNT INTO`, returned as `{ "count": 2 }`.
+                    pub fn with_count(self) -> #builder_name<Collected> {
+                        let mut new_raw_query = self.raw_query;
+                        let mut new_collect_vars = self.collect_vars;
+    
+                        new_raw_query.push(String::from("WITH COUNT INTO groupCount"));
+                        new_collect_vars.push(String::from("\"count\": groupCount"));
+    
+                        #builder_name {
+                            query_type: self.query_type,
+                            tag: Collected,
+                            bind_vars: self.bind_vars,
+                            raw_query: new_raw_query,
+                            return_clause: self.return_clause,
+                            collect_vars: new_collect_vars,
+                            update_vars: self.update_vars,
+                            update_options: self.update_options,
+                            document: self.document,
+                        }
                     }
                 }
             ]
         })
         .collect::<Vec<TokenStream2>>();
 
-    quote![
+    Ok(quote![
+        /// The methods binding a value panic if it fails to serialize to JSON,
+        /// which `#[derive(Serialize)]` types only do for maps with non-string keys.
         pub struct #builder_name<Tag: BuilderTag> {
             query_type: Option<QueryType>,
             tag: Tag,
             bind_vars: std::collections::BTreeMap<String, serde_json::Value>,
             raw_query: Vec<String>,
+            return_clause: Option<String>,
+            collect_vars: Vec<String>,
+            update_vars: Vec<String>,
+            update_options: UpdateOptions,
+            document: String,
         }
 
         impl #builder_name<EmptyBuilder> {
             pub fn new(collection_name: &str) -> Self {
                 let mut bind_vars = std::collections::BTreeMap::new();
-                bind_vars.insert(String::from("@collection"), serde_json::to_value(&collection_name).unwrap());
+                bind_vars.insert(String::from("@collection"), serde_json::Value::from(collection_name));
                 Self {
                     query_type: None,
                     tag: EmptyBuilder,
                     bind_vars,
                     raw_query: vec![],
+                    return_clause: None,
+                    collect_vars: vec![],
+                    update_vars: vec![],
+                    update_options: UpdateOptions::default(),
+                    document: String::from("item"),
                 }
             }
 
-            pub fn create(self, elem: &#struct_name) -> #builder_name<CreateQuery> {
+            /// # Panics
+            ///
+            /// Panics if `elem` fails to serialize.
+            pub fn create #struct_generics(self, elem: &#struct_type) -> #builder_name<CreateQuery>
+            #struct_bounds
+            {
                 let mut new_raw_query = self.raw_query;
                 let mut new_bind_vars = self.bind_vars;
 
                 new_raw_query.push(String::from("INSERT @elem"));
-                new_bind_vars.insert(String::from("elem"), serde_json::to_value(&elem).unwrap());
+                new_bind_vars.insert(String::from("elem"), Self::bind_value(elem));
 
                 #builder_name {
                     query_type: Some(QueryType::Create),
                     tag: CreateQuery,
                     bind_vars: new_bind_vars,
                     raw_query: new_raw_query,
+                    return_clause: self.return_clause,
+                    collect_vars: self.collect_vars,
+                    update_vars: self.update_vars,
+                    update_options: self.update_options,
+                    document: self.document,
                 }
             }
 
@@ -256,6 +564,11 @@ fn _arango_builder(struct_definition: ItemStruct) -> TokenStream2 {
                     tag: ReadQuery,
                     bind_vars: new_bind_vars,
                     raw_query: Self::for_item_in_collection(),
+                    return_clause: self.return_clause,
+                    collect_vars: self.collect_vars,
+                    update_vars: self.update_vars,
+                    update_options: self.update_options,
+                    document: self.document,
                 }
             }
 
@@ -265,6 +578,11 @@ fn _arango_builder(struct_definition: ItemStruct) -> TokenStream2 {
                     tag: UpdateQuery,
                     bind_vars: self.bind_vars,
                     raw_query: Self::for_item_in_collection(),
+                    return_clause: self.return_clause,
+                    collect_vars: self.collect_vars,
+                    update_vars: self.update_vars,
+                    update_options: self.update_options,
+                    document: self.document,
                 }
             }
 
@@ -274,6 +592,28 @@ fn _arango_builder(struct_definition: ItemStruct) -> TokenStream2 {
                     tag: DeleteQuery,
                     bind_vars: self.bind_vars,
                     raw_query: Self::for_item_in_collection(),
+                    return_clause: self.return_clause,
+                    collect_vars: self.collect_vars,
+                    update_vars: self.update_vars,
+                    update_options: self.update_options,
+                    document: self.document,
+                }
+            }
+
+            /// `UPSERT` by a search document, e.g.
+            /// `.upsert().search_key("john").insert(&person).or_update(&changes)`.
+            /// Returns `UpsertResult` documents, which tell whether the document was inserted.
+            pub fn upsert(self) -> #builder_name<UpsertQuery> {
+                #builder_name {
+                    query_type: Some(QueryType::Upsert),
+                    tag: UpsertQuery,
+                    bind_vars: self.bind_vars,
+                    raw_query: self.raw_query,
+                    return_clause: self.return_clause,
+                    collect_vars: self.collect_vars,
+                    update_vars: self.update_vars,
+                    update_options: self.update_options,
+                    document: self.document,
                 }
             }
 
@@ -282,29 +622,93 @@ fn _arango_builder(struct_definition: ItemStruct) -> TokenStream2 {
             }
         }
 
+        impl<Tag: BuilderTag> #builder_name<Tag> {
+            /// Condition builder of a nested document, used by `#[arango(nested)]` fields.
+            #[doc(hidden)]
+            pub fn nested(
+                document: String,
+                bind_vars: std::collections::BTreeMap<String, serde_json::Value>,
+                tag: Tag,
+            ) -> Self {
+                Self {
+                    query_type: None,
+                    tag,
+                    bind_vars,
+                    raw_query: vec![],
+                    return_clause: None,
+                    collect_vars: vec![],
+                    update_vars: vec![],
+                    update_options: UpdateOptions::default(),
+                    document,
+                }
+            }
+
+            #[doc(hidden)]
+            pub fn into_parts(self) -> (std::collections::BTreeMap<String, serde_json::Value>, Vec<String>) {
+                (self.bind_vars, self.raw_query)
+            }
+
+            /// The bind variable of `value`.
+            /// # Panics
+            ///
+            /// Panics if `value` fails to serialize, the fluent builder methods return no errors.
+            fn bind_value<V: Serialize + ?Sized>(value: &V) -> serde_json::Value {
+                serde_json::to_value(value).expect("a bind variable must serialize to JSON")
+            }
+        }
+
         impl<Tag: Limitable> #builder_name<Tag> {
             pub fn limit(self, limit: usize) -> #builder_name<Tag> {
                 let mut new_bind_vars = self.bind_vars;
-                new_bind_vars.insert(String::from("limit"), serde_json::to_value(&limit).unwrap());
+                new_bind_vars.insert(String::from("limit"), serde_json::Value::from(limit));
+                #builder_name {
+                    query_type: self.query_type,
+                    tag: self.tag,
+                    bind_vars: new_bind_vars,
+                    raw_query: self.raw_query,
+                    return_clause: self.return_clause,
+                    collect_vars: self.collect_vars,
+                    update_vars: self.update_vars,
+                    update_options: self.update_options,
+                    document: self.document,
+                }
+            }
+
+            /// Reads all matching documents, without the `LIMIT` of 100 set by `read`,
+            /// e.g. for the filter of `Collection::export`.
+            pub fn unlimited(self) -> #builder_name<Tag> {
+                // removed by `build`, the names of the bind variables follow their count
+                let mut new_bind_vars = self.bind_vars;
+                new_bind_vars.insert(String::from("limit"), serde_json::Value::Null);
                 #builder_name {
                     query_type: self.query_type,
                     tag: self.tag,
                     bind_vars: new_bind_vars,
-                    raw_query: self.raw_query
+                    raw_query: self.raw_query,
+                    return_clause: self.return_clause,
+                    collect_vars: self.collect_vars,
+                    update_vars: self.update_vars,
+                    update_options: self.update_options,
+                    document: self.document,
                 }
             }
         }
 
         impl<Tag: Filterable> #builder_name<Tag> {
-            pub fn filter(self) -> #builder_name<Filtering> {
+            pub fn filter(self) -> #builder_name<Filtering<Tag::Query>> {
                 let mut new_raw_query = self.raw_query;
                 new_raw_query.push(String::from("FILTER"));
 
                 #builder_name {
                     query_type: self.query_type,
-                    tag: Filtering,
+                    tag: Filtering::default(),
                     bind_vars: self.bind_vars,
                     raw_query: new_raw_query,
+                    return_clause: self.return_clause,
+                    collect_vars: self.collect_vars,
+                    update_vars: self.update_vars,
+                    update_options: self.update_options,
+                    document: self.document,
                 }
             }
         }
@@ -312,15 +716,124 @@ fn _arango_builder(struct_definition: ItemStruct) -> TokenStream2 {
         impl<Tag: Conditionable> #builder_name<Tag> {
             #(#condition_qs)*
 
+            #(#array_condition_qs)*
+
+            #(#string_condition_qs)*
+
+            #(#existence_qs)*
+
+            #(#nested_condition_qs)*
+
             #(#condition_qs_str)*
+
+            /// Parenthesized group of conditions joined by `AND`:
+            /// `.all(|g| g.name_eq(&"John").age_gt(&42))`
+            pub fn all<F>(self, group: F) -> #builder_name<Tag::Next>
+            where
+                F: FnOnce(#builder_name<AllOf>) -> #builder_name<AllOfConditional>,
+            {
+                self.group("", AllOf, group)
+            }
+
+            /// Parenthesized group of conditions joined by `OR`:
+            /// `.any(|g| g.name_eq(&"John").name_eq(&"Paul"))`
+            pub fn any<F>(self, group: F) -> #builder_name<Tag::Next>
+            where
+                F: FnOnce(#builder_name<AnyOf>) -> #builder_name<AnyOfConditional>,
+            {
+                self.group("", AnyOf, group)
+            }
+
+            /// Negated group of conditions joined by `AND`:
+            /// `.not(|g| g.name_eq(&"John").age_gt(&42))`
+            pub fn not<F>(self, group: F) -> #builder_name<Tag::Next>
+            where
+                F: FnOnce(#builder_name<AllOf>) -> #builder_name<AllOfConditional>,
+            {
+                self.group("NOT ", AllOf, group)
+            }
+
+            fn group<GroupTag: BuilderTag, GroupEnd: BuilderTag, F>(
+                self,
+                prefix: &str,
+                start: GroupTag,
+                group: F,
+            ) -> #builder_name<Tag::Next>
+            where
+                F: FnOnce(#builder_name<GroupTag>) -> #builder_name<GroupEnd>,
+            {
+                let grouped = group(#builder_name {
+                    query_type: None,
+                    tag: start,
+                    bind_vars: self.bind_vars,
+                    raw_query: vec![],
+                    return_clause: None,
+                    collect_vars: vec![],
+                    update_vars: vec![],
+                    update_options: UpdateOptions::default(),
+                    document: self.document.clone(),
+                });
+
+                #builder_name {
+                    query_type: self.query_type,
+                    tag: self.tag,
+                    bind_vars: grouped.bind_vars,
+                    raw_query: self.raw_query,
+                    return_clause: self.return_clause,
+                    collect_vars: self.collect_vars,
+                    update_vars: self.update_vars,
+                    update_options: self.update_options,
+                    document: self.document,
+                }
+                .condition(format!("{}({})", prefix, grouped.raw_query.join(" ")))
+            }
+
+            /// Binds each attribute name of a dotted `path` as `item[@filterVar2][@filterVar3]`.
+            fn attribute_path(&mut self, path: &str) -> String {
+                let mut attribute = self.document.clone();
+                for name in path.split('.') {
+                    attribute.push_str(&format!("[@{}]", self.filter_var(name)));
+                }
+                attribute
+            }
+
+            fn filter_var<V: Serialize + ?Sized>(&mut self, value: &V) -> String {
+                let bind_var_name = format!("filterVar{}", self.bind_vars.len());
+                self.bind_vars.insert(bind_var_name.clone(), Self::bind_value(value));
+                bind_var_name
+            }
+
+            fn condition(self, condition: String) -> #builder_name<Tag::Next> {
+                let mut new_raw_query = self.raw_query;
+                if let Some(connector) = Tag::CONNECTOR {
+                    new_raw_query.push(String::from(connector));
+                }
+                new_raw_query.push(condition);
+
+                #builder_name {
+                    query_type: self.query_type,
+                    tag: self.tag.next(),
+                    bind_vars: self.bind_vars,
+                    raw_query: new_raw_query,
+                    return_clause: self.return_clause,
+                    collect_vars: self.collect_vars,
+                    update_vars: self.update_vars,
+                    update_options: self.update_options,
+                    document: self.document,
+                }
+            }
         }
 
         impl<Tag: UpdateWith> #builder_name<Tag> {
-            pub fn replace_with(self, new_item: &#struct_name) -> #builder_name<UpdateField> {
+            /// # Panics
+            ///
+            /// Panics if `new_item` fails to serialize.
+            pub fn replace_with #struct_generics(self, new_item: &#struct_type) -> #builder_name<UpdateField>
+            #struct_bounds
+            {
                 let mut new_bind_vars = self.bind_vars;
                 let bind_var_name = format!("withVar{}", new_bind_vars.len());
-                new_bind_vars
-                    .insert(bind_var_name.clone(), serde_json::to_value(&new_item).unwrap());
+                new_bind_vars.insert(bind_var_name.clone(), Self::bind_value(new_item));
 
                 let mut new_raw_query = self.raw_query;
                 new_raw_query.push(format!("UPDATE item WITH @{}", bind_var_name));
@@ -330,32 +843,221 @@ fn _arango_builder(struct_definition: ItemStruct) -> TokenStream2 {
                     tag: UpdateField,
                     bind_vars: new_bind_vars,
                     raw_query: new_raw_query,
+                    return_clause: self.return_clause,
+                    collect_vars: self.collect_vars,
+                    update_vars: self.update_vars,
+                    update_options: self.update_options,
+                    document: self.document,
+                }
+            }
+        }
+
+        impl #builder_name<UpsertQuery> {
+            /// Searches the document to update by its `_key`.
+            pub fn search_key(self, key: &str) -> #builder_name<UpsertSearch> {
+                self.search(vec![(String::from("_key"), serde_json::Value::from(key))])
+            }
+
+            /// Searches the document to update by the given fields, with the values of `search`.
+            /// # Errors
+            ///
+            /// Returns `serde_json::Error` if `search` fails to serialize,
+            /// or if `fields` is empty, the search would match any document.
+            pub fn search_by #struct_generics(
+                self,
+                fields: &[#field_enum_name],
+                search: &#struct_type,
+            ) -> Result<#builder_name<UpsertSearch>, serde_json::Error>
+            #struct_bounds
+            {
+                if fields.is_empty() {
+                    return Err(<serde_json::Error as serde::ser::Error>::custom(
+                        "the search of an UPSERT has no attributes",
+                    ));
+                }
+                let search = serde_json::to_value(search)?;
+                let attributes = fields
+                    .iter()
+                    .map(|field| {
+                        let value = search.get(field.attribute()).cloned().unwrap_or_default();
+                        (field.key(), value)
+                    })
+                    .collect();
+                Ok(self.search(attributes))
+            }
+
+            /// The search document must be an object literal, its attribute values are bound.
+            fn search(self, attributes: Vec<(String, serde_json::Value)>) -> #builder_name<UpsertSearch> {
+                let mut new_bind_vars = self.bind_vars;
+                let mut members = vec![];
+                for (key, value) in attributes {
+                    let bind_var_name = format!("searchVar{}", new_bind_vars.len());
+                    members.push(format!("{}: @{}", key, bind_var_name));
+                    new_bind_vars.insert(bind_var_name, value);
+                }
+
+                let mut new_raw_query = self.raw_query;
+                new_raw_query.push(format!("UPSERT {{ {} }}", members.join(", ")));
+
+                #builder_name {
+                    query_type: self.query_type,
+                    tag: UpsertSearch,
+                    bind_vars: new_bind_vars,
+                    raw_query: new_raw_query,
+                    return_clause: self.return_clause,
+                    collect_vars: self.collect_vars,
+                    update_vars: self.update_vars,
+                    update_options: self.update_options,
+                    document: self.document,
                 }
             }
+        }
+
+        impl #builder_name<UpsertSearch> {
+            /// The document to insert when the search finds none.
+            /// # Panics
+            ///
+            /// Panics if `elem` fails to serialize.
+            pub fn insert #struct_generics(self, elem: &#struct_type) -> #builder_name<UpsertInsert>
+            #struct_bounds
+            {
+                let mut new_raw_query = self.raw_query;
+                let mut new_bind_vars = self.bind_vars;
 
+                new_raw_query.push(String::from("INSERT @insert"));
+                new_bind_vars.insert(String::from("insert"), Self::bind_value(elem));
+
+                #builder_name {
+                    query_type: self.query_type,
+                    tag: UpsertInsert,
+                    bind_vars: new_bind_vars,
+                    raw_query: new_raw_query,
+                    return_clause: self.return_clause,
+                    collect_vars: self.collect_vars,
+                    update_vars: self.update_vars,
+                    update_options: self.update_options,
+                    document: self.document,
+                }
+            }
+        }
+
+        impl #builder_name<UpsertInsert> {
+            /// Updates the found document with the attributes of `update`.
+            /// # Panics
+            ///
+            /// Panics if `update` fails to serialize.
+            pub fn or_update<U: Serialize + ?Sized>(self, update: &U) -> #builder_name<Upserted> {
+                self.upsert_with("UPDATE", Self::bind_value(update))
+            }
+
+            /// Replaces the found document with `replace`.
+            /// # Panics
+            ///
+            /// Panics if `replace` fails to serialize.
+            pub fn or_replace #struct_generics(self, replace: &#struct_type) -> #builder_name<Upserted>
+            #struct_bounds
+            {
+                self.upsert_with("REPLACE", Self::bind_value(replace))
+            }
+
+            fn upsert_with(self, operation: &str, document: serde_json::Value) -> #builder_name<Upserted> {
+                let mut new_raw_query = self.raw_query;
+                let mut new_bind_vars = self.bind_vars;
+
+                new_raw_query.push(format!("{} @with", operation));
+                new_bind_vars.insert(String::from("with"), document);
+
+                #builder_name {
+                    query_type: self.query_type,
+                    tag: Upserted,
+                    bind_vars: new_bind_vars,
+                    raw_query: new_raw_query,
+                    return_clause: self.return_clause,
+                    collect_vars: self.collect_vars,
+                    update_vars: self.update_vars,
+                    update_options: self.update_options,
+                    document: self.document,
+                }
+            }
+        }
+
+        impl<Tag: Updatable> #builder_name<Tag> {
             #(#with_qs)*
+
+            #(#expression_qs)*
+
+            /// Adds an attribute to the object of the `UPDATE`,
+            /// `member` builds it from the name of the bind variable holding `value`.
+            fn update_with<V, F>(self, member: F, value: &V) -> #builder_name<Updating>
+            where
+                V: Serialize + ?Sized,
+                F: FnOnce(&str) -> String,
+            {
+                let mut new_bind_vars = self.bind_vars;
+                let bind_var_name = format!("withVar{}", new_bind_vars.len());
+                new_bind_vars.insert(bind_var_name.clone(), Self::bind_value(value));
+
+                let mut new_update_vars = self.update_vars;
+                new_update_vars.push(member(&format!("@{}", bind_var_name)));
+
+                #builder_name {
+                    query_type: self.query_type,
+                    tag: Updating,
+                    bind_vars: new_bind_vars,
+                    raw_query: self.raw_query,
+                    return_clause: self.return_clause,
+                    collect_vars: self.collect_vars,
+                    update_vars: new_update_vars,
+                    update_options: self.update_options,
+                    document: self.document,
+                }
+            }
+        }
+
+        impl<Tag: UpdateOptionable> #builder_name<Tag> {
+            /// `keepNull: false` removes the attributes updated to `null` from the document.
+            pub fn keep_null(mut self, keep_null: bool) -> Self {
+                self.update_options = self.update_options.keep_null(keep_null);
+                self
+            }
+
+            /// `mergeObjects: false` replaces object attributes instead of merging them.
+            pub fn merge_objects(mut self, merge_objects: bool) -> Self {
+                self.update_options = self.update_options.merge_objects(merge_objects);
+                self
+            }
         }
 
         impl<Tag: LogicallyOperatable> #builder_name<Tag> {
-            pub fn and(self) -> #builder_name<LogicalOperator> {
+            pub fn and(self) -> #builder_name<LogicalOperator<Tag::Query>> {
                 let mut new_raw_query = self.raw_query;
                 new_raw_query.push(String::from("AND"));
                 #builder_name {
                     query_type: self.query_type,
-                    tag: LogicalOperator,
+                    tag: LogicalOperator::default(),
                     bind_vars: self.bind_vars,
-                    raw_query: new_raw_query
+                    raw_query: new_raw_query,
+                    return_clause: self.return_clause,
+                    collect_vars: self.collect_vars,
+                    update_vars: self.update_vars,
+                    update_options: self.update_options,
+                    document: self.document,
                 }
             }
 
-            pub fn or(self) -> #builder_name<LogicalOperator> {
+            pub fn or(self) -> #builder_name<LogicalOperator<Tag::Query>> {
                 let mut new_raw_query = self.raw_query;
                 new_raw_query.push(String::from("OR"));
                 #builder_name {
                     query_type: self.query_type,
-                    tag: LogicalOperator,
+                    tag: LogicalOperator::default(),
                     bind_vars: self.bind_vars,
-                    raw_query: new_raw_query
+                    raw_query: new_raw_query,
+                    return_clause: self.return_clause,
+                    collect_vars: self.collect_vars,
+                    update_vars: self.update_vars,
+                    update_options: self.update_options,
+                    document: self.document,
                 }
             }
         }
@@ -370,14 +1072,231 @@ fn _arango_builder(struct_definition: ItemStruct) -> TokenStream2 {
                 }
 
                 let mut new_bind_vars = self.bind_vars;
-                new_bind_vars
-                    .insert("sort_by".to_owned(), serde_json::to_value(sort_by).unwrap());
+                new_bind_vars.insert("sort_by".to_owned(), serde_json::Value::from(sort_by));
+
+                #builder_name {
+                    query_type: self.query_type,
+                    tag: Sorting,
+                    bind_vars: new_bind_vars,
+                    raw_query: new_raw_query,
+                    return_clause: self.return_clause,
+                    collect_vars: self.collect_vars,
+                    update_vars: self.update_vars,
+                    update_options: self.update_options,
+                    document: self.document,
+                }
+            }
+        }
+
+        impl<Tag: Paginable> #builder_name<Tag> {
+            /// Keyset (cursor-based) pagination of a read ordered by `field`, with `_key` as tiebreaker.
+            /// Pass the token of the previous page as `after`, or `None` for the first page.
+            pub fn keyset(
+                self,
+                field: #field_enum_name,
+                direction: SortingDirection,
+                after: Option<&KeysetToken>,
+                page_size: usize,
+            ) -> #builder_name<Sorting> {
+                let attribute = field.access();
+                let (comparison, order) = match direction {
+                    SortingDirection::Asc => (">", ""),
+                    SortingDirection::Desc => ("<", " DESC"),
+                };
+
+                let mut new_raw_query = self.raw_query;
+                let mut new_bind_vars = self.bind_vars;
+                if let Some(token) = after {
+                    new_raw_query.push(format!(
+                        "FILTER (item{0} {1} @last) OR (item{0} == @last AND item._key {1} @lastKey)",
+                        attribute, comparison
+                    ));
+                    new_bind_vars.insert(String::from("last"), token.last().clone());
+                    new_bind_vars.insert(String::from("lastKey"), serde_json::Value::from(token.last_key()));
+                }
+                new_raw_query.push(format!("SORT item{0}{1}, item._key{1}", attribute, order));
+                new_bind_vars.insert(String::from("limit"), serde_json::Value::from(page_size));
 
                 #builder_name {
                     query_type: self.query_type,
                     tag: Sorting,
                     bind_vars: new_bind_vars,
                     raw_query: new_raw_query,
+                    return_clause: self.return_clause,
+                    collect_vars: self.collect_vars,
+                    update_vars: self.update_vars,
+                    update_options: self.update_options,
+                    document: self.document,
+                }
+            }
+        }
+
+        impl<Tag: Projectable> #builder_name<Tag> {
+            /// Returns only the given fields as `RETURN { f1: item.f1, ... }`.
+            /// Decode the result into a struct holding the selected fields only:
+            /// `query.try_exec::<PersonName>(&conn)`.
+            /// # Panics
+            ///
+            /// Panics if `fields` is empty.
+            pub fn project(self, fields: &[#field_enum_name]) -> #builder_name<Projection> {
+                assert!(!fields.is_empty(), "project needs a field to return");
+                let projection = fields
+                    .iter()
+                    .map(|field| format!("{}: item{}", field.key(), field.access()))
+                    .collect::<Vec<String>>()
+                    .join(", ");
+
+                #builder_name {
+                    query_type: self.query_type,
+                    tag: Projection,
+                    bind_vars: self.bind_vars,
+                    raw_query: self.raw_query,
+                    return_clause: Some(format!("RETURN {{ {} }}", projection)),
+                    collect_vars: self.collect_vars,
+                    update_vars: self.update_vars,
+                    update_options: self.update_options,
+                    document: self.document,
+                }
+            }
+
+            /// Returns only the given fields as `RETURN KEEP(item, "f1", ...)`.
+            /// # Panics
+            ///
+            /// Panics if `fields` is empty.
+            pub fn keep(self, fields: &[#field_enum_name]) -> #builder_name<Projection> {
+                assert!(!fields.is_empty(), "keep needs a field to return");
+                let attributes = fields
+                    .iter()
+                    .map(|field| serde_json::Value::from(field.attribute()).to_string())
+                    .collect::<Vec<String>>()
+                    .join(", ");
+
+                #builder_name {
+                    query_type: self.query_type,
+                    tag: Projection,
+                    bind_vars: self.bind_vars,
+                    raw_query: self.raw_query,
+                    return_clause: Some(format!("RETURN KEEP(item, {})", attributes)),
+                    collect_vars: self.collect_vars,
+                    update_vars: self.update_vars,
+                    update_options: self.update_options,
+                    document: self.document,
+                }
+            }
+        }
+
+        impl<Tag: Collectable> #builder_name<Tag> {
+            /// Groups the documents by the given fields with `COLLECT`.
+            /// Each group is returned as an object keyed by the field names, e.g. `{ "age": 42 }`,
+            /// extended by the aggregates, the count or the documents of the group.
+            /// # Panics
+            ///
+            /// Panics if `fields` is empty, use `collect_all` for a single group.
+            pub fn group_by(self, fields: &[#field_enum_name]) -> #builder_name<Grouping> {
+                assert!(!fields.is_empty(), "group_by needs a field, use collect_all for a single group");
+                let mut new_raw_query = self.raw_query;
+                let mut new_collect_vars = self.collect_vars;
+
+                let mut groups = vec![];
+                for field in fields {
+                    let variable = format!("group{}", new_collect_vars.len());
+                    let name = field.attribute().replace('.', "_");
+                    groups.push(format!("{} = item{}", variable, field.access()));
+                    new_collect_vars.push(format!("{}: {}", serde_json::Value::from(name), variable));
+                }
+                new_raw_query.push(format!("COLLECT {}", groups.join(", ")));
+
+                #builder_name {
+                    query_type: self.query_type,
+                    tag: Grouping,
+                    bind_vars: self.bind_vars,
+                    raw_query: new_raw_query,
+                    return_clause: self.return_clause,
+                    collect_vars: new_collect_vars,
+                    update_vars: self.update_vars,
+                    update_options: self.update_options,
+                    document: self.document,
+                }
+            }
+
+            /// Collects all documents into a single group with `COLLECT`, which must be
+            /// aggregated or counted: `.collect_all().aggregate(Aggregate::Avg, PersonArangoField::Age)`.
+            pub fn collect_all(self) -> #builder_name<CollectingAll> {
+                let mut new_raw_query = self.raw_query;
+                new_raw_query.push(String::from("COLLECT"));
+
+                #builder_name {
+                    query_type: self.query_type,
+                    tag: CollectingAll,
+                    bind_vars: self.bind_vars,
+                    raw_query: new_raw_query,
+                    return_clause: self.return_clause,
+                    collect_vars: self.collect_vars,
+                    update_vars: self.update_vars,
+                    update_options: self.update_options,
+                    document: self.document,
+                }
+            }
+        }
+
+        #(#aggregatable_impls)*
+
+        impl #builder_name<Aggregating> {
+            pub fn aggregate(self, function: Aggregate, field: #field_enum_name) -> #builder_name<Aggregating> {
+                let mut new_raw_query = self.raw_query;
+                let mut new_collect_vars = self.collect_vars;
+
+                let aggregate = Self::aggregate_expression(&mut new_collect_vars, function, field);
+                if let Some(clause) = new_raw_query.last_mut() {
+                    clause.push_str(&format!(", {}", aggregate));
+                }
+
+                #builder_name {
+                    query_type: self.query_type,
+                    tag: Aggregating,
+                    bind_vars: self.bind_vars,
+                    raw_query: new_raw_query,
+                    return_clause: self.return_clause,
+                    collect_vars: new_collect_vars,
+                    update_vars: self.update_vars,
+                    update_options: self.update_options,
+                    document: self.document,
+                }
+            }
+        }
+
+        impl<Tag: BuilderTag> #builder_name<Tag> {
+            fn aggregate_expression(
+                collect_vars: &mut Vec<String>,
+                function: Aggregate,
+                field: #field_enum_name,
+            ) -> String {
+                let variable = format!("aggregate{}", collect_vars.len());
+                let name = format!("{}_{}", function.name(), field.attribute().replace('.', "_"));
+                collect_vars.push(format!("{}: {}", serde_json::Value::from(name), variable));
+                format!("{} = {}(item{})", variable, function.function(), field.access())
+            }
+        }
+
+        impl<Tag: Groupable> #builder_name<Tag> {
+            /// Returns the documents of the groups with `INTO`, as `{ "groups": [{ "item": .. }] }`.
+            pub fn into_groups(self) -> #builder_name<Collected> {
+                let mut new_raw_query = self.raw_query;
+                let mut new_collect_vars = self.collect_vars;
+
+                new_raw_query.push(String::from("INTO groupItems"));
+                new_collect_vars.push(String::from("\"groups\": groupItems"));
+
+                #builder_name {
+                    query_type: self.query_type,
+                    tag: Collected,
+                    bind_vars: self.bind_vars,
+                    raw_query: new_raw_query,
+                    return_clause: self.return_clause,
+                    collect_vars: new_collect_vars,
+                    update_vars: self.update_vars,
+                    update_options: self.update_options,
+                    document: self.document,
                 }
             }
         }
@@ -385,18 +1304,80 @@ fn _arango_builder(struct_definition: ItemStruct) -> TokenStream2 {
         impl<Tag: Buildable> #builder_name<Tag> {
             pub fn build(self) -> ArangoQuery {
                 let mut new_raw_query = self.raw_query;
+                if !self.update_vars.is_empty() {
+                    // flattened fields are bound as documents, merged with the updated attributes
+                    let (mut objects, attributes): (Vec<String>, Vec<String>) =
+                        self.update_vars.into_iter().partition(|var| var.starts_with('@'));
+                    if !attributes.is_empty() {
+                        objects.push(format!("{{ {} }}", attributes.join(", ")));
+                    }
+                    let update = if objects.len() == 1 {
+                        objects.remove(0)
+                    } else {
+                        format!("MERGE({})", objects.join(", "))
+                    };
+                    new_raw_query.push(format!("UPDATE item WITH {}", update));
+                }
+
                 let end_clause = match self.query_type {
                     Some(QueryType::Create) => "INTO @@collection RETURN NEW",
-                    Some(QueryType::Read) => "LIMIT @limit RETURN item",
-                    Some(QueryType::Update) => "IN @@collection RETURN NEW",
+                    Some(QueryType::Read) => "LIMIT @limit",
+                    Some(QueryType::Update | QueryType::Upsert) => "IN @@collection",
                     Some(QueryType::Delete) => "REMOVE item IN @@collection RETURN OLD",
                     _ => "",
                 };
-                new_raw_query.push(String::from(end_clause));
+                let mut bind_vars = self.bind_vars;
+                let unlimited = matches!(bind_vars.get("limit"), Some(serde_json::Value::Null));
+                if unlimited {
+                    bind_vars.remove("limit");
+                }
+                if !(unlimited && matches!(self.query_type, Some(QueryType::Read))) {
+                    new_raw_query.push(String::from(end_clause));
+                }
+                match self.query_type {
+                    Some(QueryType::Read) => {
+                        let return_clause = if self.collect_vars.is_empty() {
+                            self.return_clause.unwrap_or_else(|| String::from("RETURN item"))
+                        } else {
+                            format!("RETURN {{ {} }}", self.collect_vars.join(", "))
+                        };
+                        new_raw_query.push(return_clause);
+                    }
+                    Some(QueryType::Update | QueryType::Upsert) => {
+                        if let Some(options) = self.update_options.clause() {
+                            new_raw_query.push(options);
+                        }
+                        let return_clause = match self.query_type {
+                            Some(QueryType::Upsert) => "RETURN { new: NEW, inserted: OLD == null }",
+                            _ => "RETURN NEW",
+                        };
+                        new_raw_query.push(String::from(return_clause));
+                    }
+                    _ => {}
+                }
 
                 let query = new_raw_query.into_iter().map(|clause| clause + " ").collect::<String>();
 
-                ArangoQuery::with_bind_vars(&query, self.bind_vars)
+                ArangoQuery::with_bind_vars(&query, bind_vars)
+            }
+        }
+
+        #[derive(Debug, Clone, Copy, PartialEq)]
+        pub enum #field_enum_name {
+            #(#field_variants),*
+        }
+
+        impl ArangoField for #field_enum_name {
+            fn attribute(self) -> &'static str {
+                match self {
+                    #(#field_enum_attributes),*
+                }
+            }
+
+            fn access(self) -> &'static str {
+                match self {
+                    #(#field_enum_accesses),*
+                }
             }
         }
 
@@ -404,17 +1385,515 @@ fn _arango_builder(struct_definition: ItemStruct) -> TokenStream2 {
             fn query_builder(collection_name: &str) -> #builder_name<EmptyBuilder>;
         }
 
-        impl #builder_factory_name for #struct_name {
+        impl #impl_generics #builder_factory_name for #struct_type #where_clause {
             fn query_builder(collection_name: &str) -> #builder_name<EmptyBuilder> {
                 #builder_name::new(collection_name)
             }
         }
 
+        #(#nested_flat_qs)*
+
+        /// Generates the flat condition methods of `#[arango(nested)]` fields of this type.
+        #[doc(hidden)]
+        #[allow(unused_macros)]
+        macro_rules! #nested_fields_macro {
+            ($($parent:tt)*) => {
+                arango_nested_fields! { $($parent)*; #(#nested_fields),* }
+            };
+        }
+
+        #[doc(hidden)]
+        #[allow(unused_imports)]
+        pub(crate) use #nested_fields_macro;
+    ])
+}
+
+/// Condition methods of a parent builder on the fields of a nested document, like
+/// `address_city_eq` for `item.address.city`, called by the macro the derive defines per struct:
+/// `arango_nested_fields! { PersonArangoBuilder, address, ".address"; city: String => ".city" }`
+#[doc(hidden)]
+#[proc_macro]
+pub fn arango_nested_fields(input: TokenStream) -> TokenStream {
+    let NestedFields { builder, prefix, access, fields } =
+        parse_macro_input![input as NestedFields];
+    let methods = fields.iter().flat_map(|(ident, ty, field_access)| {
+        let name = format!("{}_{}", prefix, ident.to_string().trim_start_matches("r#"));
+        let call = format!("{}{}", access.value(), field_access.value());
+        let builder = &builder;
+        COMPARISONS.iter().map(move |(op_name, op)| {
+            let fn_name = Ident::new(&format!("{}_{}", name, op_name), Span::call_site());
+            let value_ty = match *op_name {
+                "in" | "not_in" => quote![[#ty]],
+                _ => quote![#ty],
+            };
+            quote![
+                #[allow(clippy::ptr_arg)]
+                pub fn #fn_name(mut self, value: &#value_ty) -> #builder<Tag::Next> {
+                    let bind_var_name = self.filter_var(&value);
+                    let condition =
+                        format!("{}{} {} @{}", self.document, #call, #op, bind_var_name);
+                    self.condition(condition)
+                }
+            ]
+        })
+    });
+    quote![
+        impl<Tag: Conditionable> #builder<Tag> {
+            #(#methods)*
+        }
     ]
+    .into()
+}
+
+/// The input of `arango_nested_fields!`.
+struct NestedFields {
+    builder: Ident,
+    prefix: Ident,
+    access: LitStr,
+    fields: Vec<(Ident, Type, LitStr)>,
+}
+
+impl parse::Parse for NestedFields {
+    fn parse(input: parse::ParseStream) -> Result<Self> {
+        let builder = input.parse()?;
+        input.parse::<Token![,]>()?;
+        let prefix = input.parse()?;
+        input.parse::<Token![,]>()?;
+        let access = input.parse()?;
+        input.parse::<Token![;]>()?;
+        let mut fields = vec![];
+        while !input.is_empty() {
+            let ident = input.parse()?;
+            input.parse::<Token![:]>()?;
+            let ty = input.parse()?;
+            input.parse::<Token![=>]>()?;
+            fields.push((ident, ty, input.parse()?));
+            if !input.is_empty() {
+                input.parse::<Token![,]>()?;
+            }
+        }
+        Ok(NestedFields { builder, prefix, access, fields })
+    }
+}
+
+/// `snake_case` field name to `CamelCase` enum variant name, e.g. `_key` to `Key`.
+fn variant_name(id: &Ident) -> String {
+    format!("{}", id)
+        .trim_start_matches("r#")
+        .split('_')
+        .filter(|part| !part.is_empty())
+        .map(|part| {
+            let mut chars = part.chars();
+            chars
+                .next()
+                .map(|c| c.to_uppercase().chain(chars).collect::<String>())
+                .unwrap_or_default()
+        })
+        .collect()
+}
+
+/// Fields marked with `#[arango(nested)]` hold a struct deriving `ArangoBuilder` as well.
+/// Their conditions are `address_city_eq` for `item.address.city`, or grouped with
+/// `address_where`. The derive of the nested struct must be in scope, i.e. in the same module
+/// before the parent, or imported with the struct, like `use crate::models::*`.
+fn is_nested(field: &Field) -> Result<bool> {
+    let mut nested = false;
+    for attr in &field.attrs {
+        match attr.parse_meta() {
+            Ok(Meta::List(list)) if list.ident == "arango" => {
+                for item in &list.nested {
+                    match item {
+                        NestedMeta::Meta(Meta::Word(word)) if word == "nested" => nested = true,
+                        _ => {
+                            return Err(Error::new_spanned(
+                                item,
+                                "unknown arango attribute, expected `nested`",
+                            ))
+                        }
+                    }
+                }
+            }
+            Ok(Meta::Word(ref ident)) | Ok(Meta::NameValue(MetaNameValue { ref ident, .. }))
+                if ident == "arango" =>
+            {
+                return Err(Error::new_spanned(attr, "expected `#[arango(nested)]`"))
+            }
+            _ => {}
+        }
+    }
+    Ok(nested)
 }
 
-fn field_id(field: &Field) -> Ident {
-    field.ident.clone().unwrap_or_else(|| {
-        panic!["Derive of Arango Builder works only for structs with named fields."]
+/// The generic parameters of the struct used by `ty`, with a `Serialize` bound on `ty`.
+/// Methods taking values of a generic field type are generic over these parameters.
+fn method_generics<T: ToTokens>(generics: &Generics, ty: &T) -> (TokenStream2, TokenStream2) {
+    let mut names = Vec::new();
+    token_names(ty.into_token_stream(), &mut names);
+
+    // parameters used by the bounds of used parameters are needed as well
+    let mut used = Vec::new();
+    loop {
+        let params = generics
+            .params
+            .iter()
+            .filter(|param| names.contains(&param_name(param)))
+            .collect::<Vec<&GenericParam>>();
+        if params.len() == used.len() {
+            break;
+        }
+        for param in &params {
+            token_names(param.into_token_stream(), &mut names);
+        }
+        used = params;
+    }
+    if used.is_empty() {
+        return (TokenStream2::new(), TokenStream2::new());
+    }
+
+    let predicates = generics
+        .where_clause
+        .iter()
+        .flat_map(|where_clause| where_clause.predicates.iter())
+        .filter(|predicate| {
+            let mut predicate_names = Vec::new();
+            token_names(predicate.into_token_stream(), &mut predicate_names);
+            predicate_names.iter().any(|name| names.contains(name))
+        });
+    (quote![<#(#used),*>], quote![where #ty: Serialize, #(#predicates),*])
+}
+
+/// The name of a generic parameter, without the `'` of lifetimes.
+fn param_name(param: &GenericParam) -> String {
+    match param {
+        GenericParam::Type(param) => format!("{}", param.ident),
+        GenericParam::Lifetime(param) => format!("{}", param.lifetime.ident),
+        GenericParam::Const(param) => format!("{}", param.ident),
+    }
+}
+
+/// Collects the identifiers of `tokens`, including the names of lifetimes.
+fn token_names(tokens: TokenStream2, names: &mut Vec<String>) {
+    for token in tokens {
+        match token {
+            proc_macro2::TokenTree::Ident(ident) => names.push(format!("{}", ident)),
+            proc_macro2::TokenTree::Group(group) => token_names(group.stream(), names),
+            _ => {}
+        }
+    }
+}
+
+/// The last path segment of a type with its generic arguments, e.g. `Vec` and `[String]` of `Vec<String>`.
+fn type_segment(ty: &Type) -> Option<(&Ident, Vec<&Type>)> {
+    match ty {
+        Type::Path(type_path) => type_path.path.segments.iter().last().map(|segment| {
+            let args = match &segment.arguments {
+                PathArguments::AngleBracketed(bracketed) => bracketed
+                    .args
+                    .iter()
+                    .filter_map(|arg| match arg {
+                        GenericArgument::Type(ty) => Some(ty),
+                        _ => None,
+                    })
+                    .collect(),
+                _ => vec![],
+            };
+            (&segment.ident, args)
+        }),
+        _ => None,
+    }
+}
+
+/// The element type of array like fields, these get array operator methods.
+fn array_element(ty: &Type) -> Option<&Type> {
+    match ty {
+        Type::Array(array) => Some(&array.elem),
+        Type::Slice(slice) => Some(&slice.elem),
+        Type::Reference(reference) => array_element(&reference.elem),
+        _ => match type_segment(ty) {
+            Some((ident, args)) if args.len() == 1 => match format!("{}", ident).as_str() {
+                "Vec" | "VecDeque" | "HashSet" | "BTreeSet" => args.first().copied(),
+                "Option" => array_element(args[0]),
+                _ => None,
+            },
+            _ => None,
+        },
+    }
+}
+
+/// The number type of numeric fields, these get increment methods.
+fn numeric_type(ty: &Type) -> Option<&Type> {
+    match type_segment(ty) {
+        Some((ident, args)) if ident == "Option" && args.len() == 1 => numeric_type(args[0]),
+        Some((ident, args)) if args.is_empty() => match format!("{}", ident).as_str() {
+            "i8" | "i16" | "i32" | "i64" | "i128" | "isize" | "u8" | "u16" | "u32" | "u64"
+            | "u128" | "usize" | "f32" | "f64" => Some(ty),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+/// `Option` fields get null check methods.
+fn is_option(ty: &Type) -> bool {
+    match type_segment(ty) {
+        Some((ident, args)) => ident == "Option" && args.len() == 1,
+        None => false,
+    }
+}
+
+/// A struct field with the attribute name serde stores it under.
+struct SerdeField<'a> {
+    field: &'a Field,
+    ident: Ident,
+    attribute: String,
+    /// The builder of `#[arango(nested)]` fields.
+    nested: Option<Ident>,
+    /// `#[serde(flatten)]` fields have no attribute of their own,
+    /// their attributes are stored in the parent document.
+    flatten: bool,
+}
+
+impl<'a> SerdeField<'a> {
+    /// `None` for fields that are not serialized, i.e. `#[serde(skip)]` or `#[serde(skip_serializing)]`.
+    fn new(field: &'a Field, rename_all: Option<&str>) -> Result<Option<Self>> {
+        let metas = serde_metas(&field.attrs);
+        let has_word = |name: &str| {
+            metas.iter().any(|meta| match meta {
+                Meta::Word(word) => word == name,
+                _ => false,
+            })
+        };
+        if has_word("skip") || has_word("skip_serializing") {
+            return Ok(None);
+        }
+
+        let ident = field.ident.clone().ok_or_else(|| {
+            Error::new_spanned(
+                field,
+                "ArangoBuilder can only be derived for structs with named fields",
+            )
+        })?;
+        let nested = if is_nested(field)? {
+            let nested_bn = nested_builder_name(&field.ty).ok_or_else(|| {
+                Error::new_spanned(
+                    &field.ty,
+                    "nested fields must hold a struct deriving ArangoBuilder",
+                )
+            })?;
+            Some(nested_bn)
+        } else {
+            None
+        };
+
+        let id = format!("{}", ident);
+        let id = id.trim_start_matches("r#");
+        let attribute = serde_name(&metas, "rename").unwrap_or_else(|| match rename_all {
+            Some(rule) => rename_field(id, rule),
+            None => String::from(id),
+        });
+
+        Ok(Some(SerdeField { field, ident, attribute, nested, flatten: has_word("flatten") }))
+    }
+
+    /// The name of the field without the `r#` of raw identifiers like `r#type`.
+    fn name(&self) -> String {
+        format!("{}", self.ident).trim_start_matches("r#").to_owned()
+    }
+
+    /// Method name of the field with a suffix, e.g. `name_eq`, also for raw identifiers like `r#type`.
+    fn method_name(&self, suffix: &str) -> Ident {
+        Ident::new(&format!("{}_{}", self.name(), suffix), Span::call_site())
+    }
+
+    /// The attribute access appended to the document, e.g. `.firstName` or `` .`first-name` ``.
+    fn access(&self) -> String {
+        if self.flatten {
+            String::new()
+        } else if is_aql_identifier(&self.attribute) {
+            format!(".{}", self.attribute)
+        } else {
+            format!(".`{}`", self.attribute.replace('\\', "\\\\").replace('`', "\\`"))
+        }
+    }
+}
+
+/// The nested items of `#[serde(...)]` attributes.
+fn serde_metas(attrs: &[Attribute]) -> Vec<Meta> {
+    attrs
+        .iter()
+        .filter_map(|attr| attr.parse_meta().ok())
+        .filter_map(|meta| match meta {
+            Meta::List(list) if list.ident == "serde" => Some(list.nested),
+            _ => None,
+        })
+        .flat_map(|nested| nested.into_iter())
+        .filter_map(|nested| match nested {
+            NestedMeta::Meta(meta) => Some(meta),
+            _ => None,
+        })
+        .collect()
+}
+
+/// The serialized name of `name = "..."` or `name(serialize = "...")`.
+fn serde_name(metas: &[Meta], name: &str) -> Option<String> {
+    metas.iter().find_map(|meta| match meta {
+        Meta::NameValue(MetaNameValue { ident, lit: Lit::Str(lit), .. }) if ident == name => {
+            Some(lit.value())
+        }
+        Meta::List(list) if list.ident == name => {
+            list.nested.iter().find_map(|nested| match nested {
+                NestedMeta::Meta(Meta::NameValue(MetaNameValue {
+                    ident,
+                    lit: Lit::Str(lit),
+                    ..
+                })) if ident == "serialize" => Some(lit.value()),
+                _ => None,
+            })
+        }
+        _ => None,
     })
 }
+
+/// The `#[serde(rename_all = "...")]` rule of the struct.
+fn serde_rename_all(attrs: &[Attribute]) -> Option<String> {
+    serde_name(&serde_metas(attrs), "rename_all")
+}
+
+/// Applies a serde `rename_all` rule to a `snake_case` field name.
+fn rename_field(name: &str, rule: &str) -> String {
+    let pascal_case = || {
+        let mut capitalize = true;
+        let mut pascal = String::new();
+        for c in name.chars() {
+            if c == '_' {
+                capitalize = true;
+            } else if capitalize {
+                pascal.push(c.to_ascii_uppercase());
+                capitalize = false;
+            } else {
+                pascal.push(c);
+            }
+        }
+        pascal
+    };
+    match rule {
+        "lowercase" => name.to_ascii_lowercase(),
+        "UPPERCASE" | "SCREAMING_SNAKE_CASE" => name.to_ascii_uppercase(),
+        "PascalCase" => pascal_case(),
+        "camelCase" => {
+            let pascal = pascal_case();
+            match pascal.chars().next() {
+                Some(first) => first.to_ascii_lowercase().to_string() + &pascal[first.len_utf8()..],
+                None => pascal,
+            }
+        }
+        "kebab-case" => name.replace('_', "-"),
+        "SCREAMING-KEBAB-CASE" => name.to_ascii_uppercase().replace('_', "-"),
+        _ => String::from(name),
+    }
+}
+
+/// Attribute names which can be used unquoted in AQL.
+fn is_aql_identifier(name: &str) -> bool {
+    const KEYWORDS: &[&str] = &[
+        "aggregate",
+        "all",
+        "and",
+        "any",
+        "asc",
+        "collect",
+        "desc",
+        "distinct",
+        "false",
+        "filter",
+        "for",
+        "graph",
+        "in",
+        "inbound",
+        "insert",
+        "into",
+        "k_shortest_paths",
+        "let",
+        "like",
+        "limit",
+        "none",
+        "not",
+        "null",
+        "or",
+        "outbound",
+        "prune",
+        "remove",
+        "replace",
+        "return",
+        "search",
+        "shortest_path",
+        "sort",
+        "true",
+        "update",
+        "upsert",
+        "window",
+        "with",
+    ];
+    let mut chars = name.chars();
+    match chars.next() {
+        Some(first) if first.is_ascii_alphabetic() || first == '_' => {
+            chars.all(|c| c.is_ascii_alphanumeric() || c == '_')
+                && !KEYWORDS.contains(&name.to_ascii_lowercase().as_str())
+        }
+        _ => false,
+    }
+}
+
+/// Attribute name as object key, quoted if necessary.
+fn aql_key(name: &str) -> String {
+    if is_aql_identifier(name) {
+        String::from(name)
+    } else {
+        aql_string(name)
+    }
+}
+
+/// Single quoted AQL string literal.
+fn aql_string(value: &str) -> String {
+    format!("'{}'", value.replace('\\', "\\\\").replace('\'', "\\'"))
+}
+
+/// Escapes the braces of `value`, to be embedded in a format string literally.
+fn format_literal(value: &str) -> String {
+    value.replace('{', "{{").replace('}', "}}")
+}
+
+/// String fields get string matching methods.
+fn is_string(ty: &Type) -> bool {
+    match ty {
+        Type::Reference(reference) => is_string(&reference.elem),
+        _ => match type_segment(ty) {
+            Some((ident, args)) => match format!("{}", ident).as_str() {
+                "String" | "str" => true,
+                "Cow" | "Option" | "Box" | "Rc" | "Arc" => args.len() == 1 && is_string(args[0]),
+                _ => false,
+            },
+            None => false,
+        },
+    }
+}
+
+/// The struct of a nested field type, e.g. `Address` of `Option<Address>`.
+fn nested_struct_name(ty: &Type) -> Option<&Ident> {
+    match type_segment(ty) {
+        Some((ident, args)) if ident == "Option" && args.len() == 1 => nested_struct_name(args[0]),
+        Some((ident, _)) => Some(ident),
+        None => None,
+    }
+}
+
+/// The builder of a nested field type, e.g. `AddressArangoBuilder` of `Option<Address>`.
+fn nested_builder_name(ty: &Type) -> Option<Ident> {
+    let ident = nested_struct_name(ty)?;
+    Some(Ident::new(&format!("{}ArangoBuilder", ident), Span::call_site()))
+}
+
+/// The macro generating the flat methods on the fields of a nested struct in a parent builder.
+fn nested_fields_macro_name(struct_name: &Ident) -> Ident {
+    Ident::new(&format!("__arangoq_nested_fields_{}", struct_name), Span::call_site())
+}
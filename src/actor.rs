@@ -1,4 +1,4 @@
-use crate::{ArangoConnection, ArangoQuery, ArangoResponse};
+use crate::{ArangoConnection, ArangoError, ArangoQuery, ArangoResponse};
 use actix::prelude::*;
 use log::debug;
 use serde::de::DeserializeOwned;
@@ -7,7 +7,7 @@ use serde::Serialize;
 pub struct DbQuery<T>(pub ArangoQuery, pub std::marker::PhantomData<T>);
 
 impl<T: 'static> Message for DbQuery<T> {
-    type Result = Result<ArangoResponse<T>, reqwest::Error>;
+    type Result = Result<ArangoResponse<T>, ArangoError>;
 }
 
 /// This is an actix async actor using reqwest async client.
@@ -45,7 +45,7 @@ impl Actor for ArangoActorAsync {
 impl<T: 'static + Serialize + DeserializeOwned + std::fmt::Debug + Send> Handler<DbQuery<T>>
     for ArangoActorAsync
 {
-    type Result = ResponseFuture<Result<ArangoResponse<T>, reqwest::Error>>;
+    type Result = ResponseFuture<Result<ArangoResponse<T>, ArangoError>>;
 
     fn handle(&mut self, msg: DbQuery<T>, _ctx: &mut Context<Self>) -> Self::Result {
         let query = msg.0;
@@ -55,10 +55,10 @@ impl<T: 'static + Serialize + DeserializeOwned + std::fmt::Debug + Send> Handler
 }
 
 impl Message for ArangoQuery {
-    type Result = Result<ArangoResponse<serde_json::Value>, reqwest::Error>;
+    type Result = Result<ArangoResponse<serde_json::Value>, ArangoError>;
 }
 impl Handler<ArangoQuery> for ArangoActorAsync {
-    type Result = ResponseFuture<Result<ArangoResponse<serde_json::Value>, reqwest::Error>>;
+    type Result = ResponseFuture<Result<ArangoResponse<serde_json::Value>, ArangoError>>;
 
     fn handle(&mut self, query: ArangoQuery, _ctx: &mut Context<Self>) -> Self::Result {
         let dbc = &self.connection;
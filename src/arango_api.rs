@@ -2,8 +2,10 @@ use super::CollectionMandatory;
 use serde::{Deserialize, Serialize};
 use serde_json::value::Value;
 use std::collections::BTreeMap;
+use std::marker::PhantomData;
+use std::time::Duration;
 
-#[derive(Debug, Default, Serialize, PartialEq)]
+#[derive(Debug, Default, Serialize, PartialEq, Clone)]
 pub struct ArangoQuery {
     pub(crate) query: String,
 
@@ -15,14 +17,85 @@ pub struct ArangoQuery {
 
     #[serde(skip_serializing_if = "Option::is_none")]
     pub(crate) options: Option<Options>,
+
+    /// Whether the results are read from and stored in the query results cache,
+    /// used if the cache mode is `demand`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) cache: Option<bool>,
+
+    /// Names the query in metrics, never sent to the server.
+    #[serde(skip)]
+    pub(crate) label: Option<String>,
+
+    /// Overrides whether the query is retried, see `RetryPolicy`.
+    #[serde(skip)]
+    pub(crate) retry: Option<bool>,
+
+    /// Overrides the timeout of the connection, see `ArangoQuery::with_timeout`.
+    #[serde(skip)]
+    pub(crate) timeout: Option<Duration>,
 }
 
 /// "options": { fullCount: true}
-#[derive(Debug, Default, Serialize, PartialEq)]
+#[derive(Debug, Default, Serialize, PartialEq, Clone)]
 pub struct Options {
     /// If set to true, ArangoDB will return the fullCount in query statistics.
     #[serde(skip_serializing_if = "Option::is_none", rename = "fullCount")]
     pub(crate) full_count: Option<bool>,
+    /// Seconds after which `ArangoDB` aborts the query, set from the timeout of the request.
+    #[serde(skip_serializing_if = "Option::is_none", rename = "maxRuntime")]
+    pub(crate) max_runtime: Option<f64>,
+}
+
+/// `OPTIONS` of the bulk queries of `UpdateMany`, `ReplaceMany` and `RemoveMany`.
+///
+/// The queries only return the documents written. The error of each document which was not
+/// written is only reported by the document API, see `Collection::update_documents`.
+/// ```ignore
+/// let options = BulkOptions::default().ignore_errors(true);
+/// ```
+#[derive(Debug, Default, Serialize, PartialEq, Clone)]
+pub struct BulkOptions {
+    /// Skips the documents which fail instead of aborting the query.
+    #[serde(skip_serializing_if = "Option::is_none", rename = "ignoreErrors")]
+    pub(crate) ignore_errors: Option<bool>,
+}
+
+/// `OPTIONS` of the bulk query of `InsertMany`, the `BulkOptions` and the `overwriteMode`.
+/// ```ignore
+/// let options = BulkInsertOptions::default().ignore_errors(true).overwrite_mode(OverwriteMode::Replace);
+/// ```
+#[derive(Debug, Default, Serialize, PartialEq, Clone)]
+pub struct BulkInsertOptions {
+    /// Skips the documents which fail instead of aborting the query.
+    #[serde(skip_serializing_if = "Option::is_none", rename = "ignoreErrors")]
+    pub(crate) ignore_errors: Option<bool>,
+    /// What happens to inserted documents with an existing `_key`.
+    #[serde(skip_serializing_if = "Option::is_none", rename = "overwriteMode")]
+    pub(crate) overwrite_mode: Option<OverwriteMode>,
+}
+
+/// `OPTIONS` of the `UPDATE` and `UPSERT` queries of the derived builder,
+/// set by its `keep_null` and `merge_objects` methods.
+#[derive(Debug, Default, PartialEq, Clone, Copy)]
+pub struct UpdateOptions {
+    /// `keepNull: false` removes the attributes updated to `null` from the document.
+    pub(crate) keep_null: Option<bool>,
+    /// `mergeObjects: false` replaces object attributes instead of merging them.
+    pub(crate) merge_objects: Option<bool>,
+}
+
+#[derive(Debug, Serialize, PartialEq, Clone, Copy)]
+#[serde(rename_all = "lowercase")]
+pub enum OverwriteMode {
+    /// Keeps the existing document.
+    Ignore,
+    /// Replaces the existing document.
+    Replace,
+    /// Updates the existing document.
+    Update,
+    /// Fails with a unique constraint violation, the default.
+    Conflict,
 }
 
 #[derive(Debug)]
@@ -90,6 +163,19 @@ pub trait Insert {
     fn insert<Elem: Serialize>(&self, elem: &Elem) -> ArangoQuery;
 }
 
+pub trait InsertMany {
+    /// The documents which fail with `ignore_errors` are skipped without a report,
+    /// only `Collection::insert_documents` reports the error of each document.
+    /// # Errors
+    ///
+    /// Returns `serde_json::Error` if `elems` fail to serialize.
+    fn insert_many<Elem: Serialize>(
+        &self,
+        elems: &[Elem],
+        options: &BulkInsertOptions,
+    ) -> Result<ArangoQuery, serde_json::Error>;
+}
+
 // Read
 
 pub trait GetAll {
@@ -124,6 +210,32 @@ pub trait Update {
     ) -> ArangoQuery;
 }
 
+pub trait UpdateMany {
+    /// The documents which fail with `ignore_errors` are skipped without a report,
+    /// only `Collection::update_documents` reports the error of each document.
+    /// # Errors
+    ///
+    /// Returns `serde_json::Error` if `updates` fail to serialize.
+    fn update_many<Key: Serialize, Update: Serialize>(
+        &self,
+        updates: &[(Key, Update)],
+        options: &BulkOptions,
+    ) -> Result<ArangoQuery, serde_json::Error>;
+}
+
+pub trait ReplaceMany {
+    /// The documents which fail with `ignore_errors` are skipped without a report,
+    /// only `Collection::replace_documents` reports the error of each document.
+    /// # Errors
+    ///
+    /// Returns `serde_json::Error` if `elems` fail to serialize.
+    fn replace_many<Key: Serialize, Elem: Serialize>(
+        &self,
+        elems: &[(Key, Elem)],
+        options: &BulkOptions,
+    ) -> Result<ArangoQuery, serde_json::Error>;
+}
+
 // Delete
 
 pub trait Remove {
@@ -131,15 +243,53 @@ pub trait Remove {
     fn remove_with_id<Id: Serialize>(&self, id: Id) -> ArangoQuery;
 }
 
+pub trait RemoveMany {
+    /// The documents which fail with `ignore_errors` are skipped without a report,
+    /// only `Collection::remove_documents` reports the error of each document.
+    /// # Errors
+    ///
+    /// Returns `serde_json::Error` if `keys` fail to serialize.
+    fn remove_many<Key: Serialize>(
+        &self,
+        keys: &[Key],
+        options: &BulkOptions,
+    ) -> Result<ArangoQuery, serde_json::Error>;
+}
+
 pub trait Truncate {
     fn truncate(&self) -> ArangoQuery;
 }
 
+// Upsert
+
+pub trait Upsert {
+    /// # Errors
+    ///
+    /// Returns `serde_json::Error` if a value fails to serialize,
+    /// or if `search` is an object without attributes, which would match any document.
+    fn upsert<Search: Serialize, Insert: Serialize, Update: Serialize>(
+        &self,
+        search: Search,
+        insert: Insert,
+        update: Update,
+    ) -> Result<ArangoQuery, serde_json::Error>;
+    /// # Errors
+    ///
+    /// Same as `upsert`.
+    fn upsert_replace<Search: Serialize, Insert: Serialize, Replace: Serialize>(
+        &self,
+        search: Search,
+        insert: Insert,
+        replace: Replace,
+    ) -> Result<ArangoQuery, serde_json::Error>;
+}
+
 pub enum QueryType {
     Create,
     Read,
     Update,
     Delete,
+    Upsert,
 }
 
 pub enum SortingDirection {
@@ -147,22 +297,121 @@ pub enum SortingDirection {
     Desc,
 }
 
+/// Aggregate functions of `COLLECT ... AGGREGATE`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Aggregate {
+    Count,
+    Sum,
+    Avg,
+    Min,
+    Max,
+    Unique,
+}
+
+/// Escapes the `LIKE` wildcards `%` and `_` of user input, to be matched literally.
+/// ```
+/// use arangoq::escape_like;
+///
+/// assert_eq!(r"100\% \_real\_", escape_like("100% _real_"));
+/// let pattern = format!("{}%", escape_like("50%"));
+/// assert_eq!(r"50\%%", pattern);
+/// ```
+#[must_use]
+pub fn escape_like(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    for c in value.chars() {
+        if let '\\' | '%' | '_' = c {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+    escaped
+}
+
+/// Implemented by the `{Struct}ArangoField` enums generated by `ArangoBuilder`,
+/// one variant per struct field.
+pub trait ArangoField: Copy {
+    /// The attribute name of the field in the stored document, following the serde renames.
+    fn attribute(self) -> &'static str;
+    /// The AQL attribute access of the field, e.g. `.firstName`, or `` .`first-name` ``
+    /// for names that need quoting.
+    fn access(self) -> &'static str;
+
+    /// The attribute name as AQL object key, quoted if necessary.
+    fn key(self) -> String {
+        if self.access().starts_with(".`") {
+            serde_json::to_string(self.attribute()).unwrap()
+        } else {
+            String::from(self.attribute())
+        }
+    }
+}
+
+/// Opaque continuation token of keyset (cursor-based) pagination.
+///
+/// Holds the sort field value and the `_key` of the last document of a page.
+/// ```ignore
+/// let page = Person::query_builder("People")
+///     .read()
+///     .keyset(PersonArangoField::Age, SortingDirection::Asc, None, 100)
+///     .build()
+///     .try_exec::<Person>(&conn)
+///     .await?;
+/// let next = page.keyset_token(PersonArangoField::Age).map(|token| token.encode());
+/// ```
+#[derive(Debug, Serialize, Deserialize, PartialEq, Clone)]
+pub struct KeysetToken {
+    #[serde(rename = "v")]
+    pub(crate) last: Value,
+    #[serde(rename = "k")]
+    pub(crate) last_key: String,
+}
+
 pub trait BuilderTag {}
 
 pub trait Buildable: BuilderTag {}
 
-pub trait Conditionable: BuilderTag {}
+pub trait Conditionable: BuilderTag {
+    /// State of the builder after a condition.
+    type Next: BuilderTag;
 
-pub trait LogicallyOperatable: BuilderTag {}
+    /// Logical operator placed before a condition, joins the conditions of `all` and `any` groups.
+    const CONNECTOR: Option<&'static str>;
 
-pub trait Filterable: BuilderTag {}
+    fn next(self) -> Self::Next;
+}
+
+pub trait LogicallyOperatable: BuilderTag {
+    /// The query the conditions joined by `and` or `or` belong to.
+    type Query: QueryKind;
+}
+
+pub trait Filterable: BuilderTag {
+    /// The query the filter belongs to.
+    type Query: QueryKind;
+}
+
+/// The queries a filter belongs to, which decide the clauses allowed after its conditions.
+pub trait QueryKind: BuilderTag {}
 
 pub trait Limitable: BuilderTag {}
 
 pub trait UpdateWith: BuilderTag {}
 
+pub trait Updatable: BuilderTag {}
+
+pub trait UpdateOptionable: BuilderTag {}
+
 pub trait Sortable: BuilderTag {}
 
+pub trait Paginable: BuilderTag {}
+
+pub trait Projectable: BuilderTag {}
+
+pub trait Collectable: BuilderTag {}
+
+pub trait Groupable: BuilderTag {}
+
 pub struct EmptyBuilder;
 
 pub struct CreateQuery;
@@ -173,16 +422,44 @@ pub struct UpdateQuery;
 
 pub struct DeleteQuery;
 
-pub struct Conditional;
+pub struct Conditional<Query>(PhantomData<Query>);
 
-pub struct Filtering;
+pub struct Filtering<Query>(PhantomData<Query>);
 
-pub struct LogicalOperator;
+pub struct LogicalOperator<Query>(PhantomData<Query>);
 
 pub struct UpdateField;
 
+pub struct Updating;
+
+pub struct UpsertQuery;
+
+pub struct UpsertSearch;
+
+pub struct UpsertInsert;
+
+pub struct Upserted;
+
 pub struct Sorting;
 
+pub struct Projection;
+
+pub struct Grouping;
+
+pub struct CollectingAll;
+
+pub struct Aggregating;
+
+pub struct Collected;
+
+pub struct AllOf;
+
+pub struct AllOfConditional;
+
+pub struct AnyOf;
+
+pub struct AnyOfConditional;
+
 impl BuilderTag for EmptyBuilder {}
 
 impl BuilderTag for CreateQuery {}
@@ -193,16 +470,62 @@ impl BuilderTag for UpdateQuery {}
 
 impl BuilderTag for DeleteQuery {}
 
-impl BuilderTag for Conditional {}
+impl<Query: QueryKind> BuilderTag for Conditional<Query> {}
 
-impl BuilderTag for Filtering {}
+impl<Query: QueryKind> BuilderTag for Filtering<Query> {}
 
-impl BuilderTag for LogicalOperator {}
+impl<Query: QueryKind> BuilderTag for LogicalOperator<Query> {}
 
 impl BuilderTag for UpdateField {}
 
+impl BuilderTag for Updating {}
+
+impl BuilderTag for UpsertQuery {}
+
+impl BuilderTag for UpsertSearch {}
+
+impl BuilderTag for UpsertInsert {}
+
+impl BuilderTag for Upserted {}
+
 impl BuilderTag for Sorting {}
 
+impl BuilderTag for Projection {}
+
+impl BuilderTag for Grouping {}
+
+impl BuilderTag for CollectingAll {}
+
+impl BuilderTag for Aggregating {}
+
+impl BuilderTag for Collected {}
+
+impl BuilderTag for AllOf {}
+
+impl BuilderTag for AllOfConditional {}
+
+impl BuilderTag for AnyOf {}
+
+impl BuilderTag for AnyOfConditional {}
+
+impl<Query> Default for Conditional<Query> {
+    fn default() -> Self {
+        Conditional(PhantomData)
+    }
+}
+
+impl<Query> Default for Filtering<Query> {
+    fn default() -> Self {
+        Filtering(PhantomData)
+    }
+}
+
+impl<Query> Default for LogicalOperator<Query> {
+    fn default() -> Self {
+        LogicalOperator(PhantomData)
+    }
+}
+
 impl Buildable for ReadQuery {}
 
 impl Buildable for DeleteQuery {}
@@ -211,36 +534,156 @@ impl Buildable for CreateQuery {}
 
 impl Buildable for UpdateField {}
 
-impl Buildable for Conditional {}
+impl Buildable for Updating {}
+
+impl Buildable for Upserted {}
+
+impl<Query: QueryKind> Buildable for Conditional<Query> {}
 
 impl Buildable for Sorting {}
 
-impl Conditionable for LogicalOperator {}
+impl Buildable for Projection {}
+
+impl Buildable for Grouping {}
+
+impl Buildable for Aggregating {}
+
+impl Buildable for Collected {}
+
+impl<Query: QueryKind> Conditionable for LogicalOperator<Query> {
+    type Next = Conditional<Query>;
+    const CONNECTOR: Option<&'static str> = None;
+
+    fn next(self) -> Self::Next {
+        Conditional::default()
+    }
+}
+
+impl<Query: QueryKind> Conditionable for Filtering<Query> {
+    type Next = Conditional<Query>;
+    const CONNECTOR: Option<&'static str> = None;
+
+    fn next(self) -> Self::Next {
+        Conditional::default()
+    }
+}
+
+impl Conditionable for AllOf {
+    type Next = AllOfConditional;
+    const CONNECTOR: Option<&'static str> = None;
+
+    fn next(self) -> Self::Next {
+        AllOfConditional
+    }
+}
+
+impl Conditionable for AllOfConditional {
+    type Next = AllOfConditional;
+    const CONNECTOR: Option<&'static str> = Some("AND");
+
+    fn next(self) -> Self::Next {
+        AllOfConditional
+    }
+}
+
+impl Conditionable for AnyOf {
+    type Next = AnyOfConditional;
+    const CONNECTOR: Option<&'static str> = None;
+
+    fn next(self) -> Self::Next {
+        AnyOfConditional
+    }
+}
 
-impl Conditionable for Filtering {}
+impl Conditionable for AnyOfConditional {
+    type Next = AnyOfConditional;
+    const CONNECTOR: Option<&'static str> = Some("OR");
 
-impl Filterable for ReadQuery {}
+    fn next(self) -> Self::Next {
+        AnyOfConditional
+    }
+}
 
-impl Filterable for UpdateQuery {}
+impl QueryKind for ReadQuery {}
 
-impl Filterable for DeleteQuery {}
+impl QueryKind for UpdateQuery {}
 
-impl Filterable for Conditional {}
+impl QueryKind for DeleteQuery {}
 
-impl Filterable for Sorting {}
+impl Filterable for ReadQuery {
+    type Query = ReadQuery;
+}
+
+impl Filterable for UpdateQuery {
+    type Query = UpdateQuery;
+}
+
+impl Filterable for DeleteQuery {
+    type Query = DeleteQuery;
+}
+
+impl<Query: QueryKind> Filterable for Conditional<Query> {
+    type Query = Query;
+}
+
+impl Filterable for Sorting {
+    type Query = ReadQuery;
+}
 
 impl Limitable for ReadQuery {}
 
-impl Limitable for Conditional {}
+impl<Query: QueryKind> Limitable for Conditional<Query> {}
 
 impl Limitable for Sorting {}
 
-impl LogicallyOperatable for Conditional {}
+impl Limitable for Projection {}
+
+impl Limitable for Grouping {}
+
+impl Limitable for Aggregating {}
 
-impl UpdateWith for Conditional {}
+impl Limitable for Collected {}
+
+impl<Query: QueryKind> LogicallyOperatable for Conditional<Query> {
+    type Query = Query;
+}
+
+impl UpdateWith for Conditional<UpdateQuery> {}
 
 impl UpdateWith for UpdateQuery {}
 
+impl Updatable for UpdateQuery {}
+
+impl Updatable for Conditional<UpdateQuery> {}
+
+impl Updatable for Updating {}
+
+impl UpdateOptionable for UpdateField {}
+
+impl UpdateOptionable for Updating {}
+
+impl UpdateOptionable for Upserted {}
+
 impl Sortable for ReadQuery {}
 
-impl Sortable for Filtering {}
+impl Sortable for Filtering<ReadQuery> {}
+
+impl Paginable for ReadQuery {}
+
+impl Paginable for Conditional<ReadQuery> {}
+
+impl Projectable for ReadQuery {}
+
+impl Projectable for Conditional<ReadQuery> {}
+
+impl Projectable for Sorting {}
+
+impl Collectable for ReadQuery {}
+
+impl Collectable for Conditional<ReadQuery> {}
+
+impl Collectable for Sorting {}
+
+impl Groupable for Grouping {}
+
+impl Groupable for Aggregating {}
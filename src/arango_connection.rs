@@ -1,11 +1,24 @@
+use crate::endpoints::{cursor_id, ClusterEndpoints, Endpoints, Route};
+use crate::error::ArangoError;
+use crate::metrics::{Metrics, MetricsRecorder};
+use crate::middleware::{Exchange, Middleware};
+use crate::result_cache::{Admission, ResultCache};
+use crate::retry::RetryPolicy;
+use crate::transport::{Method, Transport, TransportRequest, TransportResponse};
 use crate::ArangoQuery;
+use core::future::Future;
+use futures_util::future::TryFutureExt;
+use serde::de::DeserializeOwned;
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
 use std::collections::HashMap;
-use std::sync::Arc;
+use std::sync::{Arc, RwLock};
+use std::time::{Duration, Instant};
 
+#[cfg(feature = "reqwest")]
 use reqwest::{Body, Client};
 
+#[cfg(feature = "reqwest")]
 impl From<ArangoQuery> for Body {
     fn from(item: ArangoQuery) -> Self {
         let b = serde_json::to_vec(&item).unwrap();
@@ -13,16 +26,30 @@ impl From<ArangoQuery> for Body {
     }
 }
 
+/// The number of times a request follows a follower to the leader.
+const MAX_REDIRECTS: u32 = 3;
+
 /// Check <https://www.arangodb.com/docs/stable/http/database.html>
 #[derive(Clone)]
 pub struct ArangoConnection {
     pub host: Arc<String>,
     pub database: Arc<String>,
-    pub client: Arc<Client>,
+    pub transport: Arc<dyn Transport>,
+    pub middleware: Arc<Vec<Arc<dyn Middleware>>>,
+    pub retry: Option<Arc<RetryPolicy>>,
+    /// The coordinators requests are sent to instead of `host`, if any.
+    pub endpoints: Option<Arc<Endpoints>>,
+    /// The leader of an active-failover deployment, once a follower named it.
+    /// Requests go to it instead of `host` or the coordinators of `endpoints`.
+    pub leader: Arc<RwLock<Option<String>>>,
+    /// The timeout of every request, unless a query overrides it.
+    pub timeout: Option<Duration>,
+    pub result_cache: Option<Arc<ResultCache>>,
     // pub phantom: PhantomData<T>,
     pub context: Arc<Context>,
 }
 impl ArangoConnection {
+    #[cfg(feature = "reqwest")]
     #[must_use]
     pub fn new(host: String, database: String, client: Client) -> Self {
         Self::with_context(host, database, client, Context::default())
@@ -38,12 +65,33 @@ impl ArangoConnection {
     ///     arangoq::Context { app_prefix },
     /// );
     /// ```
+    #[cfg(feature = "reqwest")]
     #[must_use]
     pub fn with_context(host: String, database: String, client: Client, context: Context) -> Self {
+        Self::with_transport(host, database, Arc::new(client), context)
+    }
+    /// Same as `with_context`, sending the requests with any `Transport`.
+    /// ```ignore
+    /// let transport = Arc::new(MemoryTransport::new(|_| TransportResponse::new(200, "{}")));
+    /// let connection = ArangoConnection::with_transport(host, db_name, transport, Context::default());
+    /// ```
+    #[must_use]
+    pub fn with_transport(
+        host: String,
+        database: String,
+        transport: Arc<dyn Transport>,
+        context: Context,
+    ) -> Self {
         ArangoConnection {
             host: Arc::new(host),
             database: Arc::new(database),
-            client: Arc::new(client),
+            transport,
+            middleware: Arc::new(vec![]),
+            retry: None,
+            endpoints: None,
+            leader: Arc::new(RwLock::new(None)),
+            timeout: None,
+            result_cache: None,
             // phantom: PhantomData::<T>,
             context: Arc::new(context),
         }
@@ -56,6 +104,328 @@ impl ArangoConnection {
     pub fn collection(&self) -> String {
         format!("{}/_db/{}/_api/collection", self.host, self.database)
     }
+    #[must_use]
+    pub fn document(&self) -> String {
+        format!("{}/_db/{}/_api/document", self.host, self.database)
+    }
+    #[must_use]
+    pub fn import(&self) -> String {
+        format!("{}/_db/{}/_api/import", self.host, self.database)
+    }
+    #[must_use]
+    pub fn queries(&self) -> String {
+        format!("{}/_db/{}/_api/query", self.host, self.database)
+    }
+    #[must_use]
+    pub fn query_cache(&self) -> String {
+        format!("{}/_db/{}/_api/query-cache", self.host, self.database)
+    }
+    /// A request to `url`, authenticated with `ARANGO_USER_NAME` and `ARANGO_PASSWORD`.
+    #[must_use]
+    pub fn request(&self, method: Method, url: String) -> TransportRequest {
+        let credentials = format!(
+            "{}:{}",
+            std::env::var("ARANGO_USER_NAME").unwrap_or_default(),
+            std::env::var("ARANGO_PASSWORD").unwrap_or_default()
+        );
+        TransportRequest::new(method, url)
+            .header("authorization", &format!("Basic {}", base64::encode(credentials)))
+    }
+    /// Adds `middleware` to the end of the middleware chain of this connection.
+    #[must_use]
+    pub fn with_middleware<M: Middleware + 'static>(mut self, middleware: M) -> Self {
+        let mut chain = self.middleware.as_ref().clone();
+        chain.push(Arc::new(middleware));
+        self.middleware = Arc::new(chain);
+        self
+    }
+    /// Retries idempotent requests on transient failures with `policy`.
+    #[must_use]
+    pub fn with_retry(self, policy: RetryPolicy) -> Self {
+        Self { retry: Some(Arc::new(policy)), ..self }
+    }
+    /// Fails requests with `ArangoError::Timeout` after `timeout`, see `ArangoQuery::with_timeout`.
+    /// The server aborts queries after it as well.
+    #[must_use]
+    pub fn with_timeout(self, timeout: Duration) -> Self {
+        Self { timeout: Some(timeout), ..self }
+    }
+    /// Answers repeated read-only queries from `cache`, see `ResultCache`.
+    /// Clones of this connection share the cache.
+    #[must_use]
+    pub fn with_result_cache(self, cache: ResultCache) -> Self {
+        Self { result_cache: Some(Arc::new(cache)), ..self }
+    }
+    /// Sends the requests to the coordinators of `endpoints` instead of `host`.
+    #[must_use]
+    pub fn with_endpoints(self, endpoints: Endpoints) -> Self {
+        Self { endpoints: Some(Arc::new(endpoints)), ..self }
+    }
+    /// Replaces the coordinators of `with_endpoints` with the ones of the cluster,
+    /// read from `/_api/cluster/endpoints`. Returns their hosts.
+    /// # Errors
+    ///
+    /// Returns `ArangoError`
+    pub async fn refresh_endpoints(&self) -> Result<Vec<String>, ArangoError> {
+        let url = format!("{}/_api/cluster/endpoints", self.host);
+        let cluster: ClusterEndpoints = self.send(self.request(Method::Get, url), None).await?;
+        let hosts = cluster.hosts();
+        if let Some(endpoints) = &self.endpoints {
+            endpoints.set_hosts(hosts.clone());
+        }
+        Ok(hosts)
+    }
+    /// The host requests are sent to, the leader if a follower named it, else `host`.
+    /// # Panics
+    ///
+    /// Panics if a thread panicked while switching the leader.
+    #[must_use]
+    pub fn active_host(&self) -> String {
+        self.leader.read().unwrap().clone().unwrap_or_else(|| self.host.as_ref().clone())
+    }
+    /// Records the metrics of every request with `recorder`, see `Metrics`.
+    /// ```ignore
+    /// let conn = conn.with_metrics(Arc::new(PrometheusRecorder::new(registry)));
+    /// ```
+    #[must_use]
+    pub fn with_metrics(self, recorder: Arc<dyn MetricsRecorder>) -> Self {
+        self.with_middleware(Metrics::new(recorder))
+    }
+    /// Sends `request` through the middleware chain and the transport.
+    /// `query` is the executed query, if any, passed on to the middleware.
+    /// The returned future does not borrow the connection.
+    ///
+    /// A follower of an active-failover deployment answers with 503 and the endpoint of the leader,
+    /// then the leader becomes the active host and the request is sent to it.
+    /// The leader is forgotten when connecting to it fails.
+    ///
+    /// The cursor of a continuation which timed out is deleted, so that the server frees it.
+    ///
+    /// Read-only queries are answered from the `ResultCache` of the connection, if any,
+    /// then the middleware is called with `Middleware::cache_hit` instead.
+    /// # Errors
+    ///
+    /// Returns `ArangoError` if the transport fails.
+    /// # Panics
+    ///
+    /// Panics if a thread panicked while switching the leader.
+    pub fn execute(
+        &self,
+        mut request: TransportRequest,
+        query: Option<&ArangoQuery>,
+    ) -> impl Future<Output = Result<TransportResponse, ArangoError>> {
+        if request.timeout.is_none() {
+            request.timeout = query.and_then(|query| query.timeout).or(self.timeout);
+        }
+        let cache = self.admit(&request, query);
+        for middleware in self.middleware.iter() {
+            middleware.before_send(&mut request, query);
+        }
+        let chain = Arc::clone(&self.middleware);
+        let observed_query = query.filter(|_| !chain.is_empty()).cloned();
+        let mut observed_request = TransportRequest {
+            method: request.method,
+            url: request.url.clone(),
+            query: request.query.clone(),
+            headers: request.headers.clone(),
+            body: vec![],
+            timeout: request.timeout,
+        };
+        let cache = cache.map(|(admission, cache)| {
+            (admission, cache, on_hit(&chain, &observed_request, observed_query.as_ref()))
+        });
+        let policy = self.retry.clone().filter(|_| RetryPolicy::is_idempotent(&request, query));
+        let endpoints = self.endpoints.clone();
+        let leader = Arc::clone(&self.leader);
+        let base = Arc::clone(&self.host);
+        let transport = Arc::clone(&self.transport);
+        #[cfg(feature = "tracing")]
+        let span = crate::middleware::request_span(&request, query);
+        let received = async move {
+            let mut attempt = 1;
+            let mut failovers = 0;
+            let mut redirects = 0;
+            loop {
+                let retry = policy.as_deref().filter(|policy| attempt < policy.max_attempts);
+                // a follower may name the leader, any request is replayed then
+                let next = request.clone();
+                let active = leader.read().unwrap().clone();
+                let route = route(endpoints.as_deref(), &base, active.as_deref(), &mut request);
+                observed_request.url.clone_from(&request.url);
+                let started = Instant::now();
+                let response = transport.send(request).await;
+                if let (Some(endpoints), Some(route)) = (&endpoints, &route) {
+                    endpoints.observe(route, observed_request.method, &response);
+                }
+                let exchange = Exchange {
+                    request: &observed_request,
+                    query: observed_query.as_ref(),
+                    response: response.as_ref(),
+                    elapsed: started.elapsed(),
+                };
+                #[cfg(feature = "tracing")]
+                crate::middleware::record(&tracing::Span::current(), &exchange);
+                for middleware in chain.iter() {
+                    middleware.after_receive(&exchange);
+                }
+                let unreachable = matches!(response, Err(ArangoError::Connect(_)));
+                if unreachable && active.is_some() {
+                    let mut leader = leader.write().unwrap();
+                    if *leader == active {
+                        log::debug!("Leader {} is unreachable", active.unwrap_or_default());
+                        *leader = None;
+                    }
+                }
+                let redirect = crate::endpoints::leader_of(&response).filter(|host| {
+                    redirects < MAX_REDIRECTS
+                        && !observed_request.url.starts_with(&format!("{host}/"))
+                });
+                // a request which failed to connect was not sent, any other coordinator may take it
+                let failover = unreachable
+                    && route.as_ref().is_some_and(|route| !route.is_pinned())
+                    && failovers + 1 < endpoints.as_ref().map_or(0, |endpoints| endpoints.len());
+                request = if let Some(host) = redirect {
+                    log::info!("Switching to the leader {host}");
+                    *leader.write().unwrap() = Some(host);
+                    redirects += 1;
+                    next
+                } else if failover {
+                    log::debug!("Failing over {} after a connection error", observed_request.url);
+                    failovers += 1;
+                    next
+                } else if let Some(policy) = retry.filter(|policy| policy.is_transient(&response)) {
+                    log::debug!("Retrying {} after attempt {}", observed_request.url, attempt);
+                    transport.sleep(policy.backoff_after(attempt)).await;
+                    attempt += 1;
+                    next
+                } else {
+                    if matches!(response, Err(ArangoError::Timeout(_))) {
+                        let route = route.as_ref();
+                        delete_cursor(
+                            transport.as_ref(),
+                            endpoints.as_deref(),
+                            route,
+                            observed_request,
+                        )
+                        .await;
+                    }
+                    return response;
+                };
+            }
+        };
+        #[cfg(feature = "tracing")]
+        let received = tracing::Instrument::instrument(received, span);
+        through_cache(cache, received)
+    }
+    /// The admission of `request` to the result cache, see `ResultCache::admit`.
+    fn admit(
+        &self,
+        request: &TransportRequest,
+        query: Option<&ArangoQuery>,
+    ) -> Option<(Admission, Arc<ResultCache>)> {
+        let cache = self.result_cache.clone()?;
+        Some((cache.admit(request, query)?, cache))
+    }
+    /// Same as `execute`, deserializing the response body.
+    /// # Errors
+    ///
+    /// Returns `ArangoError`
+    pub fn send<T: DeserializeOwned>(
+        &self,
+        request: TransportRequest,
+        query: Option<&ArangoQuery>,
+    ) -> impl Future<Output = Result<T, ArangoError>> {
+        self.execute(request, query).and_then(|response| async move { response.json() })
+    }
+    /// Same as `send`, for requests whose result has no `error` field,
+    /// so an error status fails with `ArangoError::Server`.
+    /// # Errors
+    ///
+    /// Returns `ArangoError`
+    pub fn fetch<T: DeserializeOwned>(
+        &self,
+        request: TransportRequest,
+    ) -> impl Future<Output = Result<T, ArangoError>> {
+        self.execute(request, None).and_then(|response| async move { response.result() })
+    }
+}
+
+/// Sends `request`, built for `base`, to `leader` or the coordinators of `endpoints`, if any.
+fn route(
+    endpoints: Option<&Endpoints>,
+    base: &str,
+    leader: Option<&str>,
+    request: &mut TransportRequest,
+) -> Option<Route> {
+    if let Some(endpoints) = endpoints {
+        return endpoints.route(base, leader, request);
+    }
+    if let (Some(leader), Some(path)) = (leader, request.url.strip_prefix(base)) {
+        request.url = format!("{leader}{path}");
+    }
+    None
+}
+
+/// Calls `Middleware::cache_hit` of `chain`.
+fn on_hit(
+    chain: &Arc<Vec<Arc<dyn Middleware>>>,
+    request: &TransportRequest,
+    query: Option<&ArangoQuery>,
+) -> impl FnOnce() {
+    let (chain, request, query) = (Arc::clone(chain), request.clone(), query.cloned());
+    move || {
+        for middleware in chain.iter() {
+            middleware.cache_hit(&request, query.as_ref());
+        }
+    }
+}
+
+/// Answers a read from `cache` if it has a fresh result, calling `hit`, else caches the response
+/// of `received`. Invalidates the collections of a write again when it finished.
+async fn through_cache<F, H>(
+    cache: Option<(Admission, Arc<ResultCache>, H)>,
+    received: F,
+) -> Result<TransportResponse, ArangoError>
+where
+    F: Future<Output = Result<TransportResponse, ArangoError>>,
+    H: FnOnce(),
+{
+    let (key, cache, hit) = match cache {
+        Some((Admission::Read(key), cache, hit)) => (key, cache, hit),
+        Some((Admission::Write(written), cache, _)) => {
+            let response = received.await;
+            cache.written(written.as_deref());
+            return response;
+        }
+        None => return received.await,
+    };
+    if let Some(response) = cache.get(&key) {
+        hit();
+        return Ok(response);
+    }
+    let response = received.await;
+    if let Ok(response) = &response {
+        cache.insert(key, response);
+    }
+    response
+}
+
+/// Deletes the cursor of a continuation which timed out, so that the server frees it.
+async fn delete_cursor(
+    transport: &dyn Transport,
+    endpoints: Option<&Endpoints>,
+    route: Option<&Route>,
+    continuation: TransportRequest,
+) {
+    if continuation.method == Method::Delete || cursor_id(&continuation.url).is_none() {
+        return;
+    }
+    log::debug!("Deleting the cursor of {} after a timeout", continuation.url);
+    let delete = TransportRequest { method: Method::Delete, query: vec![], ..continuation };
+    let deleted = transport.send(delete).await;
+    if let (Some(endpoints), Some(route)) = (endpoints, route) {
+        endpoints.observe(route, Method::Delete, &deleted);
+    }
 }
 
 /// This struct contains all the props the db might include on top of user defined ones.
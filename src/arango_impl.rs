@@ -1,9 +1,14 @@
 use crate::arango_api::{
-    ArangoQuery, Collection, CollectionType, CursorExtractor, GetAll, GetByKey, GetByKeys, Insert,
-    Options, Remove, Replace, Truncate, Update,
+    Aggregate, ArangoQuery, BulkInsertOptions, BulkOptions, Collection, CollectionType,
+    CursorExtractor, GetAll, GetByKey, GetByKeys, Insert, InsertMany, KeysetToken, Options,
+    OverwriteMode, Remove, RemoveMany, Replace, ReplaceMany, Truncate, Update, UpdateMany,
+    UpdateOptions, Upsert,
 };
 use crate::arango_connection::ArangoConnection;
 use crate::arango_response::ArangoResponse;
+use crate::error::ArangoError;
+use crate::queries::ArangoStatus;
+use crate::transport::Method;
 use core::future::Future;
 use futures_util::future::TryFutureExt;
 use maplit::btreemap;
@@ -11,6 +16,7 @@ use serde::de::DeserializeOwned;
 use serde::Serialize;
 use serde_json::value::Value;
 use std::collections::BTreeMap;
+use std::time::Duration;
 
 #[allow(dead_code)]
 impl ArangoQuery {
@@ -54,7 +60,7 @@ impl ArangoQuery {
         bind_vars: BTreeMap<String, Value>,
         batch_size: usize,
     ) -> Self {
-        ArangoQuery { query, bind_vars, batch_size: Some(batch_size), options: None }
+        ArangoQuery { query, bind_vars, batch_size: Some(batch_size), ..Self::default() }
     }
 
     #[must_use]
@@ -65,71 +71,192 @@ impl ArangoQuery {
             bind_vars: self.bind_vars,
             batch_size: Some(batch_size),
             options: None,
+            cache: self.cache,
+            label: self.label,
+            retry: self.retry,
+            timeout: self.timeout,
         }
     }
 
     #[must_use]
     /// Enables `fullCount` in the query stats of an existing query.
     pub fn full_count(self, full_count: bool) -> Self {
-        let options = Options { full_count: Some(full_count) };
+        let options = Options { full_count: Some(full_count), ..Options::default() };
         Self {
             query: self.query,
             bind_vars: self.bind_vars,
             batch_size: self.batch_size,
             options: Some(options),
+            cache: self.cache,
+            label: self.label,
+            retry: self.retry,
+            timeout: self.timeout,
         }
     }
 
+    #[must_use]
+    /// Sets the label of this query in metrics, e.g. `"users_by_email"`.
+    pub fn with_label(self, label: &str) -> Self {
+        Self { label: Some(label.to_owned()), ..self }
+    }
+
+    #[must_use]
+    /// Retries this query on transient failures if the connection has a `RetryPolicy`,
+    /// or never with `false`. By default only read-only queries are retried,
+    /// opt in writes which are safe to repeat, e.g. an `UPSERT` or a `REPLACE` by key.
+    pub fn retry(self, retry: bool) -> Self {
+        Self { retry: Some(retry), ..self }
+    }
+
+    #[must_use]
+    /// Reads the results from the query results cache and stores them in it, see `QueryCacheMode`.
+    /// With `false` the cache is bypassed even in mode `on`.
+    pub fn cache(self, cache: bool) -> Self {
+        Self { cache: Some(cache), ..self }
+    }
+
+    #[must_use]
+    /// Fails the request of this query with `ArangoError::Timeout` after `timeout`,
+    /// instead of the timeout of the connection. The server aborts the query after it as well.
+    pub fn with_timeout(self, timeout: Duration) -> Self {
+        Self { timeout: Some(timeout), ..self }
+    }
+
+    #[must_use]
+    /// The AQL text of this query.
+    pub fn query(&self) -> &str {
+        &self.query
+    }
+
+    #[must_use]
+    /// The bind variables of this query.
+    pub fn bind_vars(&self) -> &BTreeMap<String, Value> {
+        &self.bind_vars
+    }
+
+    #[must_use]
+    /// The label of this query in metrics, if set.
+    pub fn label(&self) -> Option<&str> {
+        self.label.as_deref()
+    }
+
     /// Executes this query using the provided `ArangoConnection`.
     /// Returns `ArangoResponse`
     /// # Errors
     ///
-    /// Returns `reqwest::Error`
-    /// Note: `reqwest::Error` is temporarily exposed and may change in the future.
+    /// Returns `ArangoError`
     pub fn try_exec<T: Serialize + DeserializeOwned>(
         &self,
         dbc: &ArangoConnection,
-    ) -> impl Future<Output = Result<ArangoResponse<T>, reqwest::Error>> {
+    ) -> impl Future<Output = Result<ArangoResponse<T>, ArangoError>> {
         let nm = format!("{:?}", self);
-        dbc.client
-            .post(dbc.cursor().as_str())
-            .header("content-type", "application/json")
-            .json(self)
-            .basic_auth(
-                // TODO add this to ArangoConnection as well
-                std::env::var("ARANGO_USER_NAME").unwrap_or_default(),
-                std::env::var("ARANGO_PASSWORD").ok(),
-            )
-            .send()
-            .and_then(reqwest::Response::json)
-            .map_err(move |err| {
-                log::debug!("Error during db request: {} Query: {:?}", err, nm);
-                err
-            })
+        let request = dbc.request(Method::Post, dbc.cursor());
+        let request = match self.timeout.or(dbc.timeout) {
+            // the server aborts the query when the client gives up on it
+            Some(timeout) => {
+                let max_runtime = Some(timeout.as_secs_f64());
+                let options = Options { max_runtime, ..self.options.clone().unwrap_or_default() };
+                request.json(&Self { options: Some(options), ..self.clone() })
+            }
+            None => request.json(self),
+        };
+        dbc.send(request, Some(self)).map_err(move |err| {
+            log::debug!("Error during db request: {} Query: {:?}", err, nm);
+            err
+        })
     }
 }
 
 impl CursorExtractor {
-    /// TODO: document this
+    /// Fetches the next batch of the cursor.
+    /// # Errors
+    ///
+    /// Returns `ArangoError`
     pub fn next<T: Serialize + DeserializeOwned>(
         &self,
         dbc: &ArangoConnection,
-    ) -> impl Future<Output = Result<ArangoResponse<T>, reqwest::Error>> {
+    ) -> impl Future<Output = Result<ArangoResponse<T>, ArangoError>> {
         let nm = format!("{:?}", self);
-        dbc.client
-            .put(&format!["{}/{}", dbc.cursor().as_str(), self.0])
-            .basic_auth(
-                // TODO add this to ArangoConnection as well
-                std::env::var("ARANGO_USER_NAME").unwrap_or_default(),
-                std::env::var("ARANGO_PASSWORD").ok(),
-            )
-            .send()
-            .and_then(reqwest::Response::json)
+        dbc.send(dbc.request(Method::Put, format!["{}/{}", dbc.cursor().as_str(), self.0]), None)
             .map_err(move |err| {
                 log::debug!("Error during db request: {} Query: {:?}", err, nm);
                 err
             })
     }
+
+    /// Deletes the cursor on the server before it is exhausted, freeing its resources.
+    /// # Errors
+    ///
+    /// Returns `ArangoError`
+    pub fn delete(
+        &self,
+        dbc: &ArangoConnection,
+    ) -> impl Future<Output = Result<ArangoStatus, ArangoError>> {
+        dbc.send(dbc.request(Method::Delete, format!["{}/{}", dbc.cursor().as_str(), self.0]), None)
+    }
+}
+
+impl Aggregate {
+    #[must_use]
+    /// The AQL aggregate function.
+    pub fn function(self) -> &'static str {
+        match self {
+            Aggregate::Count => "COUNT",
+            Aggregate::Sum => "SUM",
+            Aggregate::Avg => "AVG",
+            Aggregate::Min => "MIN",
+            Aggregate::Max => "MAX",
+            Aggregate::Unique => "UNIQUE",
+        }
+    }
+
+    #[must_use]
+    /// Prefix of the aggregated value in the returned groups, e.g. `sum` in `sum_age`.
+    pub fn name(self) -> &'static str {
+        match self {
+            Aggregate::Count => "count",
+            Aggregate::Sum => "sum",
+            Aggregate::Avg => "avg",
+            Aggregate::Min => "min",
+            Aggregate::Max => "max",
+            Aggregate::Unique => "unique",
+        }
+    }
+}
+
+impl KeysetToken {
+    #[must_use]
+    /// ```ignore
+    /// let token = KeysetToken::new(serde_json::json!(42), "1234");
+    /// ```
+    pub fn new(last: Value, last_key: &str) -> Self {
+        Self { last, last_key: last_key.to_owned() }
+    }
+
+    #[must_use]
+    /// Encodes the token into a url safe string to be handed out to clients,
+    /// unpadded url safe base64 of its JSON.
+    pub fn encode(&self) -> String {
+        let json = serde_json::to_vec(self).unwrap_or_default();
+        base64::encode_config(json, base64::URL_SAFE_NO_PAD)
+    }
+
+    #[must_use]
+    /// Decodes a token produced by `encode`, returns `None` for malformed input.
+    pub fn decode(token: &str) -> Option<Self> {
+        let json = base64::decode_config(token, base64::URL_SAFE_NO_PAD).ok()?;
+        serde_json::from_slice(&json).ok()
+    }
+
+    #[must_use]
+    pub fn last(&self) -> &Value {
+        &self.last
+    }
+
+    #[must_use]
+    pub fn last_key(&self) -> &str {
+        &self.last_key
+    }
 }
 
 impl Collection {
@@ -313,3 +440,226 @@ impl Truncate for Collection {
         )
     }
 }
+
+impl Upsert for Collection {
+    /// Inserts `insert`, or updates the document matching `search` with `update`.
+    /// `search` is either a key or an object of the attributes to match.
+    /// ```ignore
+    /// let query = coll.upsert("Paul", &TestUser::new("Paul"), &Instrument { instrument: String::from("bass") })?;
+    /// let upserted = query.exec::<UpsertResult<TestUser>>(&conn).await?;
+    /// ```
+    fn upsert<Search: Serialize, Insert: Serialize, Update: Serialize>(
+        &self,
+        search: Search,
+        insert: Insert,
+        update: Update,
+    ) -> Result<ArangoQuery, serde_json::Error> {
+        let mut bind_vars = btreemap![
+            String::from("@collection") => Value::String(self.name.clone()),
+            String::from("insert") => serde_json::to_value(&insert)?,
+            String::from("update") => serde_json::to_value(&update)?,
+        ];
+        let query = format!(
+            "UPSERT {} INSERT @insert UPDATE @update IN @@collection RETURN {{ new: NEW, inserted: OLD == null }}",
+            search_literal(&search, &mut bind_vars)?
+        );
+        Ok(ArangoQuery::with_bind_vars(&query, bind_vars))
+    }
+
+    /// Inserts `insert`, or replaces the document matching `search` with `replace`.
+    /// ```ignore
+    /// let query = coll.upsert_replace("Paul", &TestUser::new("Paul"), &TestUser::new("Paul McCartney"))?;
+    /// ```
+    fn upsert_replace<Search: Serialize, Insert: Serialize, Replace: Serialize>(
+        &self,
+        search: Search,
+        insert: Insert,
+        replace: Replace,
+    ) -> Result<ArangoQuery, serde_json::Error> {
+        let mut bind_vars = btreemap![
+            String::from("@collection") => Value::String(self.name.clone()),
+            String::from("insert") => serde_json::to_value(&insert)?,
+            String::from("replace") => serde_json::to_value(&replace)?,
+        ];
+        let query = format!(
+            "UPSERT {} INSERT @insert REPLACE @replace IN @@collection RETURN {{ new: NEW, inserted: OLD == null }}",
+            search_literal(&search, &mut bind_vars)?
+        );
+        Ok(ArangoQuery::with_bind_vars(&query, bind_vars))
+    }
+}
+
+impl BulkOptions {
+    #[must_use]
+    pub fn ignore_errors(self, ignore_errors: bool) -> Self {
+        Self { ignore_errors: Some(ignore_errors) }
+    }
+}
+
+impl BulkInsertOptions {
+    #[must_use]
+    pub fn ignore_errors(self, ignore_errors: bool) -> Self {
+        Self { ignore_errors: Some(ignore_errors), ..self }
+    }
+
+    #[must_use]
+    pub fn overwrite_mode(self, overwrite_mode: OverwriteMode) -> Self {
+        Self { overwrite_mode: Some(overwrite_mode), ..self }
+    }
+}
+
+/// The `OPTIONS` clause of a bulk query, empty without options.
+fn bulk_clause<Options: Serialize + Default + PartialEq>(
+    options: &Options,
+) -> Result<String, serde_json::Error> {
+    if *options == Options::default() {
+        Ok(String::new())
+    } else {
+        Ok(format!(" OPTIONS {}", serde_json::to_string(options)?))
+    }
+}
+
+impl UpdateOptions {
+    #[must_use]
+    pub fn keep_null(self, keep_null: bool) -> Self {
+        Self { keep_null: Some(keep_null), ..self }
+    }
+
+    #[must_use]
+    pub fn merge_objects(self, merge_objects: bool) -> Self {
+        Self { merge_objects: Some(merge_objects), ..self }
+    }
+
+    /// The `OPTIONS` clause, `None` without options.
+    #[must_use]
+    pub fn clause(&self) -> Option<String> {
+        let options = [("keepNull", self.keep_null), ("mergeObjects", self.merge_objects)]
+            .iter()
+            .filter_map(|(name, value)| value.map(|value| format!("{name}: {value}")))
+            .collect::<Vec<String>>();
+        if options.is_empty() {
+            None
+        } else {
+            Some(format!("OPTIONS {{ {} }}", options.join(", ")))
+        }
+    }
+}
+
+impl InsertMany for Collection {
+    /// ```ignore
+    /// let query = coll.insert_many(&users, &BulkInsertOptions::default().ignore_errors(true))?;
+    /// let inserted = query.exec::<TestUser>(&conn).await?;
+    /// ```
+    fn insert_many<Elem: Serialize>(
+        &self,
+        elems: &[Elem],
+        options: &BulkInsertOptions,
+    ) -> Result<ArangoQuery, serde_json::Error> {
+        let query = format!(
+            "FOR doc IN @docs INSERT doc INTO @@collection{} RETURN NEW",
+            bulk_clause(options)?
+        );
+        Ok(ArangoQuery::with_bind_vars(
+            &query,
+            btreemap![
+                String::from("@collection") => Value::String(self.name.clone()),
+                String::from("docs") => serde_json::to_value(elems)?,
+            ],
+        ))
+    }
+}
+
+impl UpdateMany for Collection {
+    /// ```ignore
+    /// let query = coll.update_many(&[("Paul", &bass), ("John", &guitar)], &BulkOptions::default())?;
+    /// ```
+    fn update_many<Key: Serialize, Update: Serialize>(
+        &self,
+        updates: &[(Key, Update)],
+        options: &BulkOptions,
+    ) -> Result<ArangoQuery, serde_json::Error> {
+        let query = format!(
+            "FOR doc IN @docs UPDATE doc[0] WITH doc[1] IN @@collection{} RETURN NEW",
+            bulk_clause(options)?
+        );
+        Ok(ArangoQuery::with_bind_vars(
+            &query,
+            btreemap![
+                String::from("@collection") => Value::String(self.name.clone()),
+                String::from("docs") => serde_json::to_value(updates)?,
+            ],
+        ))
+    }
+}
+
+impl ReplaceMany for Collection {
+    /// ```ignore
+    /// let query = coll.replace_many(&[("Paul", TestUser::new("Paul McCartney"))], &BulkOptions::default())?;
+    /// ```
+    fn replace_many<Key: Serialize, Elem: Serialize>(
+        &self,
+        elems: &[(Key, Elem)],
+        options: &BulkOptions,
+    ) -> Result<ArangoQuery, serde_json::Error> {
+        let query = format!(
+            "FOR doc IN @docs REPLACE doc[0] WITH doc[1] IN @@collection{} RETURN NEW",
+            bulk_clause(options)?
+        );
+        Ok(ArangoQuery::with_bind_vars(
+            &query,
+            btreemap![
+                String::from("@collection") => Value::String(self.name.clone()),
+                String::from("docs") => serde_json::to_value(elems)?,
+            ],
+        ))
+    }
+}
+
+impl RemoveMany for Collection {
+    /// ```ignore
+    /// let query = coll.remove_many(&["Paul", "John"], &BulkOptions::default().ignore_errors(true))?;
+    /// ```
+    fn remove_many<Key: Serialize>(
+        &self,
+        keys: &[Key],
+        options: &BulkOptions,
+    ) -> Result<ArangoQuery, serde_json::Error> {
+        let query = format!(
+            "FOR doc IN @docs REMOVE doc IN @@collection{} RETURN OLD",
+            bulk_clause(options)?
+        );
+        Ok(ArangoQuery::with_bind_vars(
+            &query,
+            btreemap![
+                String::from("@collection") => Value::String(self.name.clone()),
+                String::from("docs") => serde_json::to_value(keys)?,
+            ],
+        ))
+    }
+}
+
+/// The search document of `UPSERT` must be an object literal, so the attribute values
+/// are bound one by one as `{ "name": @search0 }`. Any other value than an object is a key.
+/// An empty object is refused, `UPSERT {  }` would update an arbitrary document.
+fn search_literal<Search: Serialize>(
+    search: &Search,
+    bind_vars: &mut BTreeMap<String, Value>,
+) -> Result<String, serde_json::Error> {
+    let attributes: Vec<(String, Value)> = match serde_json::to_value(search)? {
+        Value::Object(attributes) => attributes.into_iter().collect(),
+        key => vec![(String::from("_key"), key)],
+    };
+    if attributes.is_empty() {
+        return Err(serde::ser::Error::custom("the search of an UPSERT has no attributes"));
+    }
+    let members = attributes
+        .into_iter()
+        .enumerate()
+        .map(|(index, (name, value))| {
+            let bind_var_name = format!("search{index}");
+            bind_vars.insert(bind_var_name.clone(), value);
+            Ok(format!("{}: @{}", serde_json::to_string(&name)?, bind_var_name))
+        })
+        .collect::<Result<Vec<String>, serde_json::Error>>()?;
+    Ok(format!("{{ {} }}", members.join(", ")))
+}
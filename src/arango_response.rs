@@ -1,3 +1,4 @@
+use crate::arango_api::{ArangoField, KeysetToken};
 use serde::{Deserialize, Serialize};
 
 #[derive(Debug, PartialEq, Serialize, Deserialize, Default)]
@@ -25,6 +26,19 @@ pub struct ArangoResponse<T> {
     pub id: String,
 }
 
+impl<T: Serialize> ArangoResponse<T> {
+    /// Builds the continuation token of keyset pagination from the last document of the result.
+    ///
+    /// Returns `None` on an empty result, which marks the end of the pagination,
+    /// or when the last document has no `field` or `_key` attribute.
+    pub fn keyset_token<F: ArangoField>(&self, field: F) -> Option<KeysetToken> {
+        let last = serde_json::to_value(self.result.last()?).ok()?;
+        let value = last.get(field.attribute())?;
+        let key = last.get("_key")?.as_str()?;
+        Some(KeysetToken::new(value.clone(), key))
+    }
+}
+
 #[cfg(test)]
 #[allow(clippy::too_many_arguments)]
 impl<T> ArangoResponse<T> {
@@ -43,6 +57,68 @@ impl<T> ArangoResponse<T> {
     }
 }
 
+/// Result of an `UPSERT`: the new document, and whether it was inserted or an existing one updated.
+#[derive(Debug, PartialEq, Serialize, Deserialize, Clone)]
+pub struct UpsertResult<T> {
+    pub new: T,
+    pub inserted: bool,
+}
+
+/// Result of the document API for one document of a bulk write like `Collection::insert_documents`:
+/// the new document of inserts, updates and replaces, the old document of removes,
+/// or the error of a document which was not written.
+#[derive(Debug, PartialEq, Serialize, Deserialize, Clone)]
+pub struct BulkResult<T> {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub new: Option<T>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub old: Option<T>,
+    #[serde(default)]
+    pub error: bool,
+    #[serde(rename = "errorNum", skip_serializing, default)]
+    pub error_num: u64,
+    #[serde(rename = "errorMessage", skip_serializing_if = "String::is_empty", default)]
+    pub error_message: String,
+}
+
+/// A document of a bulk write which was not written, by its index in the written slice.
+#[derive(Debug, PartialEq, Clone)]
+pub struct BulkFailure {
+    pub index: usize,
+    pub error_num: u64,
+    pub error_message: String,
+}
+
+/// The results of a bulk write, one per document in the order of the written slice.
+#[derive(Debug, PartialEq, Serialize, Deserialize, Clone)]
+#[serde(transparent)]
+pub struct BulkResponse<T> {
+    pub results: Vec<BulkResult<T>>,
+}
+
+impl<T> BulkResponse<T> {
+    /// The documents which were not written, with their error.
+    #[must_use]
+    pub fn failures(&self) -> Vec<BulkFailure> {
+        self.results
+            .iter()
+            .enumerate()
+            .filter(|(_, result)| result.error)
+            .map(|(index, result)| BulkFailure {
+                index,
+                error_num: result.error_num,
+                error_message: result.error_message.clone(),
+            })
+            .collect()
+    }
+
+    /// The indices of the documents which were not written.
+    #[must_use]
+    pub fn failed_indices(&self) -> Vec<usize> {
+        self.failures().into_iter().map(|failure| failure.index).collect()
+    }
+}
+
 #[derive(Debug, PartialEq, Serialize, Deserialize, Default)]
 pub struct ResponseExtra {
     #[serde(default)]
@@ -5,18 +5,39 @@ mod tests {
     // use crate::test::*;
     // use crate::*;
     use crate::arango_api::{
-        ArangoQuery, Buildable, BuilderTag, Collection, CollectionType, Conditionable, Conditional,
+        escape_like, Aggregate, Aggregating, AllOf, AllOfConditional, AnyOf, AnyOfConditional,
+        ArangoField, ArangoQuery, Buildable, BuilderTag, BulkInsertOptions, BulkOptions,
+        Collectable, Collected, CollectingAll, Collection, CollectionType, Conditionable,
         CreateQuery, CursorExtractor, DeleteQuery, Edge, EmptyBuilder, ExecuteArangoQuery,
-        Filterable, Filtering, GetAll, GetByKey, GetByKeys, Insert, Limitable, LogicalOperator,
-        LogicallyOperatable, QueryType, ReadQuery, Remove, Replace, Sortable, Sorting,
-        SortingDirection, Truncate, Update, UpdateField, UpdateQuery, UpdateWith,
+        Filterable, Filtering, GetAll, GetByKey, GetByKeys, Groupable, Grouping, Insert,
+        InsertMany, KeysetToken, Limitable, LogicalOperator, LogicallyOperatable, OverwriteMode,
+        Paginable, Projectable, Projection, QueryType, ReadQuery, Remove, RemoveMany, Replace,
+        ReplaceMany, Sortable, Sorting, SortingDirection, Truncate, Updatable, Update, UpdateField,
+        UpdateMany, UpdateOptionable, UpdateOptions, UpdateQuery, UpdateWith, Updating, Upsert,
+        UpsertInsert, UpsertQuery, UpsertSearch, Upserted,
     };
-    use crate::arango_connection::{ArangoConnection, CollectionMandatory};
-    use crate::arango_response::{ArangoResponse, ResponseExtra};
-    use crate::test::ArangoMock;
-    use crate::ArangoBuilder;
+    use crate::arango_connection::{ArangoConnection, CollectionMandatory, Context};
+    use crate::arango_response::{ArangoResponse, BulkFailure, ResponseExtra, UpsertResult};
+    use crate::database::Database;
+    use crate::dump::DumpError;
+    use crate::endpoints::Endpoints;
+    use crate::error::ArangoError;
+    #[cfg(feature = "reqwest")]
+    use crate::import::{ImportOptions, ImportSummary, ImportType, OnDuplicate};
+    use crate::metrics::{
+        CACHE_HITS, DOCUMENTS_SCANNED, ERRORS, EXECUTION_TIME, REQUEST_DURATION, WRITES,
+    };
+    use crate::middleware::{Exchange, Middleware};
+    use crate::queries::{QueryCacheMode, QueryCacheProperties, QueryTracking};
+    use crate::result_cache::{CacheStats, ResultCache};
+    use crate::retry::{is_read_only, RetryPolicy};
+    use crate::test::{ArangoMock, MemoryRecorder, MemoryTransport};
+    use crate::transport::{Method, TransportRequest, TransportResponse};
+    use crate::{arango_nested_fields, ArangoBuilder};
     use maplit::hashmap;
+    #[cfg(feature = "reqwest")]
     use mockito::{mock, Matcher};
+    #[cfg(feature = "reqwest")]
     use reqwest::Client;
     use serde::{Deserialize, Serialize};
     use std::collections::BTreeMap;
@@ -104,6 +125,170 @@ mod tests {
         assert_eq!(expected, serde_json::to_string(&query).unwrap());
     }
 
+    #[test]
+    fn test_collection_upsert() {
+        #[derive(Serialize)]
+        struct Instrument {
+            instrument: String,
+        }
+
+        let query = test_collection()
+            .upsert(
+                "Paul",
+                TestUser::new("Paul McCartney"),
+                Instrument { instrument: String::from("bass") },
+            )
+            .unwrap();
+        let expected = r#"{"query":"UPSERT { \"_key\": @search0 } INSERT @insert UPDATE @update IN @@collection RETURN { new: NEW, inserted: OLD == null }","bindVars":{"@collection":"Beatles","insert":{"name":"Paul McCartney"},"search0":"Paul","update":{"instrument":"bass"}}}"#;
+        assert_eq!(expected, serde_json::to_string(&query).unwrap());
+
+        let query = test_collection()
+            .upsert_replace(
+                TestUser::new("Paul McCartney"),
+                TestUser::new("Paul McCartney"),
+                TestUser::new("Sir Paul McCartney"),
+            )
+            .unwrap();
+        let expected = r#"{"query":"UPSERT { \"name\": @search0 } INSERT @insert REPLACE @replace IN @@collection RETURN { new: NEW, inserted: OLD == null }","bindVars":{"@collection":"Beatles","insert":{"name":"Paul McCartney"},"replace":{"name":"Sir Paul McCartney"},"search0":"Paul McCartney"}}"#;
+        assert_eq!(expected, serde_json::to_string(&query).unwrap());
+
+        let empty = test_collection().upsert(
+            serde_json::json!({}),
+            TestUser::new("Paul McCartney"),
+            Instrument { instrument: String::from("bass") },
+        );
+        assert!(empty.unwrap_err().to_string().contains("no attributes"));
+
+        let upserted: ArangoResponse<UpsertResult<TestUser>> = serde_json::from_str(
+            r#"{"result":[{"new":{"name":"Paul McCartney"},"inserted":true}],"code":201}"#,
+        )
+        .unwrap();
+        assert!(upserted.result[0].inserted);
+        assert_eq!("Paul McCartney", upserted.result[0].new.name);
+    }
+
+    #[test]
+    fn test_collection_bulk() {
+        #[derive(Serialize)]
+        struct Instrument {
+            instrument: String,
+        }
+
+        let users = vec![TestUser::new("Paul McCartney"), TestUser::new("John Lennon")];
+        let query = test_collection()
+            .insert_many(
+                &users,
+                &BulkInsertOptions::default()
+                    .ignore_errors(true)
+                    .overwrite_mode(OverwriteMode::Replace),
+            )
+            .unwrap();
+        let expected = r#"{"query":"FOR doc IN @docs INSERT doc INTO @@collection OPTIONS {\"ignoreErrors\":true,\"overwriteMode\":\"replace\"} RETURN NEW","bindVars":{"@collection":"Beatles","docs":[{"name":"Paul McCartney"},{"name":"John Lennon"}]}}"#;
+        assert_eq!(expected, serde_json::to_string(&query).unwrap());
+
+        let query = test_collection()
+            .update_many(
+                &[("Paul", Instrument { instrument: String::from("bass") })],
+                &BulkOptions::default(),
+            )
+            .unwrap();
+        let expected = r#"{"query":"FOR doc IN @docs UPDATE doc[0] WITH doc[1] IN @@collection RETURN NEW","bindVars":{"@collection":"Beatles","docs":[["Paul",{"instrument":"bass"}]]}}"#;
+        assert_eq!(expected, serde_json::to_string(&query).unwrap());
+
+        let query = test_collection()
+            .replace_many(&[("Paul", TestUser::new("Paul McCartney"))], &BulkOptions::default())
+            .unwrap();
+        let expected = r#"{"query":"FOR doc IN @docs REPLACE doc[0] WITH doc[1] IN @@collection RETURN NEW","bindVars":{"@collection":"Beatles","docs":[["Paul",{"name":"Paul McCartney"}]]}}"#;
+        assert_eq!(expected, serde_json::to_string(&query).unwrap());
+
+        let query = test_collection()
+            .remove_many(&["Paul", "John"], &BulkOptions::default().ignore_errors(false))
+            .unwrap();
+        let expected = r#"{"query":"FOR doc IN @docs REMOVE doc IN @@collection OPTIONS {\"ignoreErrors\":false} RETURN OLD","bindVars":{"@collection":"Beatles","docs":["Paul","John"]}}"#;
+        assert_eq!(expected, serde_json::to_string(&query).unwrap());
+
+        // maps with non-string keys fail to serialize
+        let update: HashMap<(u8, u8), u8> = hashmap! { (1, 2) => 3 };
+        assert!(test_collection()
+            .update_many(&[("Paul", update)], &BulkOptions::default())
+            .is_err());
+    }
+
+    #[actix_rt::test]
+    async fn test_bulk_documents() {
+        #[derive(Serialize)]
+        struct Instrument {
+            instrument: String,
+        }
+
+        let transport = std::sync::Arc::new(MemoryTransport::new(|request| match request.method {
+            Method::Post => TransportResponse::new(
+                202,
+                r#"[{"_key":"1","new":{"name":"Paul McCartney"}},{"error":true,"errorNum":1210,"errorMessage":"unique constraint violated"},{"_key":"3","new":{"name":"George Harrison"}}]"#,
+            ),
+            Method::Patch => TransportResponse::new(
+                202,
+                r#"[{"error":true,"errorNum":1202,"errorMessage":"document not found"}]"#,
+            ),
+            _ => TransportResponse::new(
+                404,
+                r#"{"error":true,"code":404,"errorNum":1203,"errorMessage":"collection or view not found"}"#,
+            ),
+        }));
+        let conn = ArangoConnection::with_transport(
+            "http://arango".to_string(),
+            "evt_test".to_string(),
+            transport.clone(),
+            Context::default(),
+        );
+        let users = vec![
+            TestUser::new("Paul McCartney"),
+            TestUser::new("John Lennon"),
+            TestUser::new("George Harrison"),
+        ];
+        let inserted = test_collection()
+            .insert_documents::<TestUser, _>(&conn, &users, Some(OverwriteMode::Conflict))
+            .await
+            .unwrap();
+        assert_eq!(
+            Some("George Harrison"),
+            inserted.results[2].new.as_ref().map(|user| user.name.as_str())
+        );
+        assert_eq!(vec![1], inserted.failed_indices());
+        assert_eq!(
+            vec![BulkFailure {
+                index: 1,
+                error_num: 1210,
+                error_message: String::from("unique constraint violated"),
+            }],
+            inserted.failures()
+        );
+
+        let updates = [("Ringo", Instrument { instrument: String::from("drums") })];
+        let updated =
+            test_collection().update_documents::<TestUser, _, _>(&conn, &updates).await.unwrap();
+        assert_eq!(1202, updated.failures()[0].error_num);
+        match test_collection().remove_documents::<serde_json::Value, _>(&conn, &["Paul"]).await {
+            Err(ArangoError::Server(status)) => assert_eq!(1203, status.error_num),
+            other => panic!("expected a server error, got {:?}", other),
+        }
+
+        let requests = transport.requests();
+        assert_eq!("http://arango/_db/evt_test/_api/document/Beatles", requests[0].url);
+        assert_eq!(
+            vec![
+                (String::from("returnNew"), String::from("true")),
+                (String::from("overwriteMode"), String::from("conflict")),
+            ],
+            requests[0].query
+        );
+        assert_eq!(br#"[{"_key":"Ringo","instrument":"drums"}]"#.to_vec(), requests[1].body);
+        assert_eq!(
+            (Method::Delete, br#"["Paul"]"#.to_vec()),
+            (requests[2].method, requests[2].body.clone())
+        );
+    }
+
     #[test]
     fn test_collection_remove() {
         let query = test_collection().remove("Paul");
@@ -279,110 +464,753 @@ mod tests {
         }
     }
 
-    #[derive(Debug, PartialEq, Serialize, Deserialize)]
-    struct U {
-        _key: String,
-        _id: String,
-        _rev: String,
-        name: String,
-    }
-
-    impl U {
-        pub fn new(_key: &str, _id: &str, _rev: &str, name: &str) -> Self {
-            Self {
-                _key: String::from(_key),
-                _id: String::from(_id),
-                _rev: String::from(_rev),
-                name: String::from(name),
-            }
+    #[test]
+    fn test_generated_filter_groups() {
+        #[derive(ArangoBuilder, Serialize)]
+        pub struct Person {
+            name: &'static str,
+            age: u8,
         }
-    }
 
-    fn test_response() -> ArangoResponse<U> {
-        ArangoResponse::new(
-            vec![U::new("13221", "Characters/13221", "_ZEJkt1W---", "John Doe")],
-            false,
-            false,
-            ResponseExtra::new(0, 0, 0, 0, 0, 0, 3.654_956_817_626_953e-4, 2, 2107, vec![]),
-            false,
-            201,
-            String::new(),
-            0,
-            String::default(),
-        )
-    }
+        let collection_name = "People";
 
-    fn test_response_json() -> &'static str {
-        r#"{"result":[{"_key":"13221","_id":"Characters/13221","_rev":"_ZEJkt1W---","name":"John Doe"}],"hasMore":false,"cached":false,"extra":{"stats":{"writesExecuted":0,"writesIgnored":0,"scannedFull":0,"scannedIndex":0,"filtered":0,"httpRequests":0,"executionTime":0.0003654956817626953,"fullCount":2,"peakMemoryUsage":2107},"warnings":[]},"error":false,"code":201}"#
+        let query1 = Person::query_builder(collection_name)
+            .read()
+            .filter()
+            .age_gt(&42)
+            .and()
+            .any(|g| g.name_eq(&"John Lennon").name_eq(&"George Harrison"))
+            .build();
+        let query2 = Person::query_builder(collection_name)
+            .delete()
+            .filter()
+            .not(|g| g.name_eq(&"Ringo Starr").any(|h| h.age_lt(&20).age_gt(&80)))
+            .or()
+            .all(|g| g.age_ge(&20).age_le(&30))
+            .build();
+
+        let values = vec![
+            (
+                query1,
+                r#"{"query":"FOR item IN @@collection FILTER item.age > @filterVar2 AND (item.name == @filterVar3 OR item.name == @filterVar4) LIMIT @limit RETURN item ","bindVars":{"@collection":"People","filterVar2":42,"filterVar3":"John Lennon","filterVar4":"George Harrison","limit":100}}"#,
+            ),
+            (
+                query2,
+                r#"{"query":"FOR item IN @@collection FILTER NOT (item.name == @filterVar1 AND (item.age < @filterVar2 OR item.age > @filterVar3)) OR (item.age >= @filterVar4 AND item.age <= @filterVar5) REMOVE item IN @@collection RETURN OLD ","bindVars":{"@collection":"People","filterVar1":"Ringo Starr","filterVar2":20,"filterVar3":80,"filterVar4":20,"filterVar5":30}}"#,
+            ),
+        ];
+
+        for (query, expected) in values {
+            assert_eq!(expected, serde_json::to_string(&query).unwrap());
+        }
     }
 
     #[test]
-    fn test_arango_response_ser_deser() {
-        assert_eq!(test_response_json(), serde_json::to_string(&test_response()).unwrap());
+    fn test_generated_nested_and_array_filters() {
+        #[derive(ArangoBuilder, Serialize)]
+        pub struct Address {
+            city: &'static str,
+            zip: u32,
+        }
 
-        assert_eq!(
-            test_response(),
-            serde_json::from_str(&serde_json::to_string(&test_response()).unwrap()).unwrap()
-        );
+        #[derive(ArangoBuilder, Serialize)]
+        pub struct Person {
+            name: &'static str,
+            #[arango(nested)]
+            address: Address,
+            instruments: Vec<&'static str>,
+        }
 
-        assert_eq!(
-            test_response(),
-            serde_json::from_slice(&serde_json::to_vec(&test_response()).unwrap()).unwrap()
-        );
-    }
+        let collection_name = "People";
 
-    #[test]
-    fn test_arango_mock() {
-        let query = || Collection::new("Characters", CollectionType::Document).get_by_key("13221");
-        let query_json = || serde_json::to_string(&query()).unwrap();
-        let test_mock = ArangoMock::new(hashmap![query_json() => test_response_json().to_owned()]);
-        assert_eq!(test_response_json(), &test_mock.execute_query(query()));
-    }
+        let query1 = Person::query_builder(collection_name)
+            .read()
+            .filter()
+            .address_where(|a| a.city_eq(&"Liverpool").any(|z| z.zip_lt(&1000).zip_gt(&9000)))
+            .build();
+        let query2 = Person::query_builder(collection_name)
+            .read()
+            .filter()
+            .instruments_any_eq(&"bass")
+            .and()
+            .instruments_all_in(&["bass", "guitar", "piano"])
+            .or()
+            .instruments_none_eq(&"drums")
+            .and()
+            .instruments_length_ge(2)
+            .build();
+        let query3 = Person::query_builder(collection_name)
+            .read()
+            .filter()
+            ._eq("address.city", &"Liverpool")
+            .and()
+            ._in("name", &["John Lennon", "Paul McCartney"])
+            .build();
+        let query4 = Person::query_builder(collection_name)
+            .read()
+            .filter()
+            .address_city_eq(&"Liverpool")
+            .and()
+            .address_zip_in(&[1000, 2000])
+            .build();
 
-    #[cfg(feature = "actixclient")]
-    #[test]
-    fn test_arango_connection() {
-        use std::sync::Arc;
-        let aconn = ArangoConnection {
-            host: Arc::new("http://localhost:8529/_api/cursor".to_owned()),
-            client: Arc::new(actix_web::client::Client::new()),
-        };
-        let query = Collection::new("Characters", CollectionType::Document).get_by_key("13221");
-        // let real_res: Result<ArangoResponse<TestUser>, actix_web::Error> = query.exec(&aconn);
-        // assert!(real_res.is_err());
+        let values = vec![
+            (
+                query1,
+                r#"{"query":"FOR item IN @@collection FILTER (item.address.city == @filterVar2 AND (item.address.zip < @filterVar3 OR item.address.zip > @filterVar4)) LIMIT @limit RETURN item ","bindVars":{"@collection":"People","filterVar2":"Liverpool","filterVar3":1000,"filterVar4":9000,"limit":100}}"#,
+            ),
+            (
+                query2,
+                r#"{"query":"FOR item IN @@collection FILTER item.instruments ANY == @filterVar2 AND item.instruments ALL IN @filterVar3 OR item.instruments NONE == @filterVar4 AND LENGTH(item.instruments) >= @filterVar5 LIMIT @limit RETURN item ","bindVars":{"@collection":"People","filterVar2":"bass","filterVar3":["bass","guitar","piano"],"filterVar4":"drums","filterVar5":2,"limit":100}}"#,
+            ),
+            (
+                query3,
+                r#"{"query":"FOR item IN @@collection FILTER item[@filterVar2][@filterVar3] == @filterVar4 AND item[@filterVar5] IN @filterVar6 LIMIT @limit RETURN item ","bindVars":{"@collection":"People","filterVar2":"address","filterVar3":"city","filterVar4":"Liverpool","filterVar5":"name","filterVar6":["John Lennon","Paul McCartney"],"limit":100}}"#,
+            ),
+            (
+                query4,
+                r#"{"query":"FOR item IN @@collection FILTER item.address.city == @filterVar2 AND item.address.zip IN @filterVar3 LIMIT @limit RETURN item ","bindVars":{"@collection":"People","filterVar2":"Liverpool","filterVar3":[1000,2000],"limit":100}}"#,
+            ),
+        ];
 
-        let client = actix_web::client::Client::new();
-        let aconn = ArangoConnection::new("http://localhost:8529/_api/cursor".to_owned(), client);
-        let query = Collection::new("Characters", CollectionType::Document).get_by_key("13221");
-        // let real_res: Result<ArangoResponse<TestUser>, actix_web::Error> = query.exec(&aconn);
-        // assert!(real_res.is_err());
+        for (query, expected) in values {
+            assert_eq!(expected, serde_json::to_string(&query).unwrap());
+        }
     }
 
-    /// a test for "missing field `hasMore` at line 1 column 85"
     #[test]
-    fn test_arangoresponse() {
-        let responses = vec![
-            r#"{"code":400,"error":true,"errorMessage":"expecting POST /_api/cursor","errorNum":400}"#,
+    fn test_generated_string_filters() {
+        #[derive(ArangoBuilder, Serialize)]
+        pub struct Person {
+            name: String,
+            nickname: Option<String>,
+            age: u8,
+        }
+
+        let collection_name = "People";
+
+        let query1 = Person::query_builder(collection_name)
+            .read()
+            .filter()
+            .name_like(&format!("{}%", escape_like("John_")))
+            .and()
+            .nickname_not_like("%o%")
+            .or()
+            .name_regex("^Paul")
+            .build();
+        let query2 = Person::query_builder(collection_name)
+            .read()
+            .filter()
+            .name_starts_with("Geo")
+            .and()
+            .name_contains("Star")
+            .and()
+            .any(|g| g.name_eq_ci("ringo starr").name_like_ci("%LENNON"))
+            .build();
+        let query3 = Person::query_builder(collection_name)
+            .read()
+            .filter()
+            .name_regex_ci("^john")
+            .and()
+            .nickname_starts_with_ci("THE")
+            .and()
+            .nickname_contains_ci("quiet")
+            .build();
+
+        let values = vec![
+            (
+                query1,
+                r#"{"query":"FOR item IN @@collection FILTER item.name LIKE @filterVar2 AND item.nickname NOT LIKE @filterVar3 OR item.name =~ @filterVar4 LIMIT @limit RETURN item ","bindVars":{"@collection":"People","filterVar2":"John\\_%","filterVar3":"%o%","filterVar4":"^Paul","limit":100}}"#,
+            ),
+            (
+                query2,
+                r#"{"query":"FOR item IN @@collection FILTER STARTS_WITH(item.name, @filterVar2) AND CONTAINS(item.name, @filterVar3) AND (LOWER(item.name) == LOWER(@filterVar4) OR LIKE(item.name, @filterVar5, true)) LIMIT @limit RETURN item ","bindVars":{"@collection":"People","filterVar2":"Geo","filterVar3":"Star","filterVar4":"ringo starr","filterVar5":"%LENNON","limit":100}}"#,
+            ),
+            (
+                query3,
+                r#"{"query":"FOR item IN @@collection FILTER REGEX_TEST(item.name, @filterVar2, true) AND STARTS_WITH(LOWER(item.nickname), LOWER(@filterVar3)) AND CONTAINS(LOWER(item.nickname), LOWER(@filterVar4)) LIMIT @limit RETURN item ","bindVars":{"@collection":"People","filterVar2":"^john","filterVar3":"THE","filterVar4":"quiet","limit":100}}"#,
+            ),
         ];
-        for r in responses {
-            let resp: Result<ArangoResponse<String>, serde_json::error::Error> =
-                serde_json::from_str(r);
-            assert!(resp.is_ok());
+
+        for (query, expected) in values {
+            assert_eq!(expected, serde_json::to_string(&query).unwrap());
         }
     }
 
     #[test]
-    fn test_collection_mandatory() {
-        let key = "1234";
+    fn test_generated_serde_attributes() {
+        #[derive(ArangoBuilder, Serialize)]
+        pub struct Audit {
+            created_by: &'static str,
+        }
 
-        assert_eq!(
-            CollectionMandatory {
-                _key: String::from(key),
-                _id: String::default(),
-                _rev: String::default(),
-                _old_rev: String::default(),
-                extra: HashMap::new(),
-            },
+        #[derive(ArangoBuilder, Serialize)]
+        #[serde(rename_all = "camelCase")]
+        pub struct Account {
+            first_name: &'static str,
+            #[serde(rename = "e-mail")]
+            email: &'static str,
+            #[serde(skip)]
+            #[allow(dead_code)]
+            session: Option<String>,
+            #[arango(nested)]
+            #[serde(flatten)]
+            audit: Audit,
+        }
+
+        let collection_name = "Accounts";
+
+        let query1 = Account::query_builder(collection_name)
+            .read()
+            .filter()
+            .first_name_eq(&"John")
+            .and()
+            .email_exists()
+            .and()
+            .audit_where(|audit| audit.created_by_eq(&"admin"))
+            .project(&[AccountArangoField::FirstName, AccountArangoField::Email])
+            .build();
+        let query2 = Account::query_builder(collection_name)
+            .update()
+            .filter()
+            .first_name_eq(&"John")
+            .email(&"john@example.com")
+            .build();
+        let query3 = Account::query_builder(collection_name)
+            .update()
+            .audit(&Audit { created_by: "root" })
+            .build();
+
+        let values = vec![
+            (
+                query1,
+                r#"{"query":"FOR item IN @@collection FILTER item.firstName == @filterVar2 AND HAS(item, 'e-mail') AND (item.created_by == @filterVar3) LIMIT @limit RETURN { firstName: item.firstName, \"e-mail\": item.`e-mail` } ","bindVars":{"@collection":"Accounts","filterVar2":"John","filterVar3":"admin","limit":100}}"#,
+            ),
+            (
+                query2,
+                r#"{"query":"FOR item IN @@collection FILTER item.firstName == @filterVar1 UPDATE item WITH { 'e-mail': @withVar2 } IN @@collection RETURN NEW ","bindVars":{"@collection":"Accounts","filterVar1":"John","withVar2":"john@example.com"}}"#,
+            ),
+            (
+                query3,
+                r#"{"query":"FOR item IN @@collection UPDATE item WITH @withVar1 IN @@collection RETURN NEW ","bindVars":{"@collection":"Accounts","withVar1":{"created_by":"root"}}}"#,
+            ),
+        ];
+
+        for (query, expected) in values {
+            assert_eq!(expected, serde_json::to_string(&query).unwrap());
+        }
+        assert_eq!("e-mail", AccountArangoField::Email.attribute());
+        assert_eq!(".`e-mail`", AccountArangoField::Email.access());
+    }
+
+    #[test]
+    fn test_generated_generic_struct() {
+        #[derive(ArangoBuilder, Serialize)]
+        pub struct Tagged<'a, T>
+        where
+            T: Clone,
+        {
+            name: &'a str,
+            value: T,
+            tags: Vec<T>,
+        }
+
+        let collection_name = "Tagged";
+
+        let query1 = Tagged::<u8>::query_builder(collection_name)
+            .read()
+            .filter()
+            .name_eq(&"answer")
+            .and()
+            .value_gt(&41)
+            .and()
+            .tags_any_eq(&42)
+            .build();
+        let query2 = Tagged::<u8>::query_builder(collection_name)
+            .create(&Tagged { name: "answer", value: 42, tags: vec![42] })
+            .build();
+        let query3 = Tagged::<u8>::query_builder(collection_name).update().value(&42).build();
+
+        let values = vec![
+            (
+                query1,
+                r#"{"query":"FOR item IN @@collection FILTER item.name == @filterVar2 AND item.value > @filterVar3 AND item.tags ANY == @filterVar4 LIMIT @limit RETURN item ","bindVars":{"@collection":"Tagged","filterVar2":"answer","filterVar3":41,"filterVar4":42,"limit":100}}"#,
+            ),
+            (
+                query2,
+                r#"{"query":"INSERT @elem INTO @@collection RETURN NEW ","bindVars":{"@collection":"Tagged","elem":{"name":"answer","tags":[42],"value":42}}}"#,
+            ),
+            (
+                query3,
+                r#"{"query":"FOR item IN @@collection UPDATE item WITH { value: @withVar1 } IN @@collection RETURN NEW ","bindVars":{"@collection":"Tagged","withVar1":42}}"#,
+            ),
+        ];
+
+        for (query, expected) in values {
+            assert_eq!(expected, serde_json::to_string(&query).unwrap());
+        }
+    }
+
+    #[test]
+    fn test_generated_combined_updates() {
+        #[derive(ArangoBuilder, Serialize)]
+        pub struct Counter {
+            name: &'static str,
+            count: u32,
+            tags: Vec<&'static str>,
+        }
+
+        let collection_name = "Counters";
+
+        let query1 = Counter::query_builder(collection_name)
+            .update()
+            .filter()
+            .name_eq(&"visits")
+            .name(&"page visits")
+            .count_increment(&1)
+            .tags_remove_all(&["draft", "old"])
+            .keep_null(false)
+            .merge_objects(false)
+            .build();
+        let query2 = Counter::query_builder(collection_name)
+            .update()
+            .replace_with(&Counter { name: "visits", count: 0, tags: vec![] })
+            .keep_null(true)
+            .keep_null(false)
+            .build();
+
+        let values = vec![
+            (
+                query1,
+                r#"{"query":"FOR item IN @@collection FILTER item.name == @filterVar1 UPDATE item WITH { name: @withVar2, count: item.count + @withVar3, tags: REMOVE_VALUES(item.tags, @withVar4) } IN @@collection OPTIONS { keepNull: false, mergeObjects: false } RETURN NEW ","bindVars":{"@collection":"Counters","filterVar1":"visits","withVar2":"page visits","withVar3":1,"withVar4":["draft","old"]}}"#,
+            ),
+            (
+                query2,
+                r#"{"query":"FOR item IN @@collection UPDATE item WITH @withVar1 IN @@collection OPTIONS { keepNull: false } RETURN NEW ","bindVars":{"@collection":"Counters","withVar1":{"count":0,"name":"visits","tags":[]}}}"#,
+            ),
+        ];
+
+        for (query, expected) in values {
+            assert_eq!(expected, serde_json::to_string(&query).unwrap());
+        }
+    }
+
+    #[test]
+    fn test_generated_upsert() {
+        #[derive(ArangoBuilder, Serialize)]
+        pub struct Person {
+            name: &'static str,
+            age: u8,
+        }
+
+        #[derive(Serialize)]
+        struct Age {
+            age: u8,
+        }
+
+        let collection_name = "People";
+        let john = Person { name: "John Lennon", age: 40 };
+
+        let query1 = Person::query_builder(collection_name)
+            .upsert()
+            .search_key("john")
+            .insert(&john)
+            .or_update(&Age { age: 41 })
+            .keep_null(false)
+            .build();
+        let query2 = Person::query_builder(collection_name)
+            .upsert()
+            .search_by(&[PersonArangoField::Name], &john)
+            .unwrap()
+            .insert(&john)
+            .or_replace(&john)
+            .build();
+
+        let values = vec![
+            (
+                query1,
+                r#"{"query":"UPSERT { _key: @searchVar1 } INSERT @insert UPDATE @with IN @@collection OPTIONS { keepNull: false } RETURN { new: NEW, inserted: OLD == null } ","bindVars":{"@collection":"People","insert":{"age":40,"name":"John Lennon"},"searchVar1":"john","with":{"age":41}}}"#,
+            ),
+            (
+                query2,
+                r#"{"query":"UPSERT { name: @searchVar1 } INSERT @insert REPLACE @with IN @@collection RETURN { new: NEW, inserted: OLD == null } ","bindVars":{"@collection":"People","insert":{"age":40,"name":"John Lennon"},"searchVar1":"John Lennon","with":{"age":40,"name":"John Lennon"}}}"#,
+            ),
+        ];
+
+        for (query, expected) in values {
+            assert_eq!(expected, serde_json::to_string(&query).unwrap());
+        }
+
+        // a search without attributes would match any document
+        let search = Person::query_builder(collection_name).upsert().search_by(&[], &john);
+        assert!(search.is_err());
+        // maps with non-string keys fail to serialize
+        let update: HashMap<(u8, u8), u8> = hashmap! { (1, 2) => 3 };
+        let upsert = std::panic::catch_unwind(|| {
+            Person::query_builder(collection_name)
+                .upsert()
+                .search_key("john")
+                .insert(&john)
+                .or_update(&update)
+        });
+        assert!(upsert.is_err());
+    }
+
+    #[test]
+    fn test_generated_existence_filters() {
+        #[derive(ArangoBuilder, Serialize)]
+        pub struct Person {
+            name: String,
+            nickname: Option<String>,
+            #[serde(rename = "note{0}")]
+            note: Option<String>,
+        }
+
+        let collection_name = "People";
+
+        let query1 = Person::query_builder(collection_name)
+            .read()
+            .filter()
+            .name_exists()
+            .and()
+            .nickname_is_null()
+            .build();
+        let query2 = Person::query_builder(collection_name)
+            .delete()
+            .filter()
+            .nickname_missing()
+            .or()
+            .nickname_is_not_null()
+            .build();
+        let query3 =
+            Person::query_builder(collection_name).read().filter().note_is_not_null().build();
+
+        let values = vec![
+            (
+                query1,
+                r#"{"query":"FOR item IN @@collection FILTER HAS(item, 'name') AND (HAS(item, 'nickname') AND item.nickname == null) LIMIT @limit RETURN item ","bindVars":{"@collection":"People","limit":100}}"#,
+            ),
+            (
+                query2,
+                r#"{"query":"FOR item IN @@collection FILTER NOT HAS(item, 'nickname') OR item.nickname != null REMOVE item IN @@collection RETURN OLD ","bindVars":{"@collection":"People"}}"#,
+            ),
+            (
+                query3,
+                r#"{"query":"FOR item IN @@collection FILTER item.`note{0}` != null LIMIT @limit RETURN item ","bindVars":{"@collection":"People","limit":100}}"#,
+            ),
+        ];
+
+        for (query, expected) in values {
+            assert_eq!(expected, serde_json::to_string(&query).unwrap());
+        }
+    }
+
+    #[test]
+    fn test_generated_keyset_pagination() {
+        #[derive(ArangoBuilder, Serialize)]
+        pub struct Person {
+            _key: &'static str,
+            name: &'static str,
+            age: u8,
+        }
+
+        let collection_name = "People";
+
+        let first_page = Person::query_builder(collection_name)
+            .read()
+            .keyset(PersonArangoField::Age, SortingDirection::Asc, None, 2)
+            .build();
+        assert_eq!(
+            r#"{"query":"FOR item IN @@collection SORT item.age, item._key LIMIT @limit RETURN item ","bindVars":{"@collection":"People","limit":2}}"#,
+            serde_json::to_string(&first_page).unwrap()
+        );
+
+        let response = ArangoResponse::new(
+            vec![
+                Person { _key: "1", name: "John Lennon", age: 40 },
+                Person { _key: "2", name: "George Harrison", age: 58 },
+            ],
+            false,
+            false,
+            ResponseExtra::default(),
+            false,
+            201,
+            String::new(),
+            0,
+            String::default(),
+        );
+        let token = response.keyset_token(PersonArangoField::Age).unwrap();
+        assert_eq!(KeysetToken::new(serde_json::json!(58), "2"), token);
+
+        let next_page = Person::query_builder(collection_name)
+            .read()
+            .filter()
+            .name_ne(&"Ringo Starr")
+            .keyset(PersonArangoField::Age, SortingDirection::Desc, Some(&token), 2)
+            .build();
+        assert_eq!(
+            r#"{"query":"FOR item IN @@collection FILTER item.name != @filterVar2 FILTER (item.age < @last) OR (item.age == @last AND item._key < @lastKey) SORT item.age DESC, item._key DESC LIMIT @limit RETURN item ","bindVars":{"@collection":"People","filterVar2":"Ringo Starr","last":58,"lastKey":"2","limit":2}}"#,
+            serde_json::to_string(&next_page).unwrap()
+        );
+
+        let last_page = ArangoResponse::<Person>::new(
+            vec![],
+            false,
+            false,
+            ResponseExtra::default(),
+            false,
+            201,
+            String::new(),
+            0,
+            String::default(),
+        );
+        assert_eq!(None, last_page.keyset_token(PersonArangoField::Age));
+        assert_eq!("_key", PersonArangoField::Key.attribute());
+    }
+
+    #[test]
+    fn test_generated_projection() {
+        #[derive(ArangoBuilder, Serialize)]
+        pub struct Person {
+            name: &'static str,
+            age: u8,
+            email: &'static str,
+        }
+
+        #[derive(Deserialize, Debug, PartialEq)]
+        struct PersonName {
+            name: String,
+            age: u8,
+        }
+
+        let collection_name = "People";
+
+        let query1 = Person::query_builder(collection_name)
+            .read()
+            .filter()
+            .age_gt(&42)
+            .project(&[PersonArangoField::Name, PersonArangoField::Age])
+            .limit(10)
+            .build();
+        let query2 = Person::query_builder(collection_name)
+            .read()
+            .sort("name", SortingDirection::Asc)
+            .keep(&[PersonArangoField::Name, PersonArangoField::Age])
+            .build();
+
+        assert_eq!(
+            r#"{"query":"FOR item IN @@collection FILTER item.age > @filterVar2 LIMIT @limit RETURN { name: item.name, age: item.age } ","bindVars":{"@collection":"People","filterVar2":42,"limit":10}}"#,
+            serde_json::to_string(&query1).unwrap()
+        );
+        assert_eq!(
+            r#"{"query":"FOR item IN @@collection SORT item.@sort_by LIMIT @limit RETURN KEEP(item, \"name\", \"age\") ","bindVars":{"@collection":"People","limit":100,"sort_by":"name"}}"#,
+            serde_json::to_string(&query2).unwrap()
+        );
+
+        let response: ArangoResponse<PersonName> =
+            serde_json::from_str(r#"{"result":[{"name":"John Lennon","age":40}],"code":201}"#)
+                .unwrap();
+        assert_eq!(
+            vec![PersonName { name: String::from("John Lennon"), age: 40 }],
+            response.result
+        );
+    }
+
+    #[test]
+    fn test_generated_collect() {
+        #[derive(ArangoBuilder, Serialize)]
+        pub struct Person {
+            name: &'static str,
+            age: u8,
+            city: &'static str,
+        }
+
+        #[derive(Deserialize, Debug, PartialEq)]
+        struct CityGroup {
+            city: String,
+            count: usize,
+        }
+
+        let collection_name = "People";
+
+        let query1 = Person::query_builder(collection_name)
+            .read()
+            .filter()
+            .age_gt(&18)
+            .group_by(&[PersonArangoField::City])
+            .with_count()
+            .build();
+        let query2 = Person::query_builder(collection_name)
+            .read()
+            .group_by(&[PersonArangoField::City, PersonArangoField::Age])
+            .aggregate(Aggregate::Count, PersonArangoField::Name)
+            .aggregate(Aggregate::Unique, PersonArangoField::Name)
+            .into_groups()
+            .limit(10)
+            .build();
+        let query3 = Person::query_builder(collection_name)
+            .read()
+            .collect_all()
+            .aggregate(Aggregate::Avg, PersonArangoField::Age)
+            .aggregate(Aggregate::Max, PersonArangoField::Age)
+            .build();
+        let query4 = Person::query_builder(collection_name)
+            .read()
+            .filter()
+            .age_gt(&18)
+            .collect_all()
+            .with_count()
+            .build();
+
+        let values = vec![
+            (
+                query1,
+                r#"{"query":"FOR item IN @@collection FILTER item.age > @filterVar2 COLLECT group0 = item.city WITH COUNT INTO groupCount LIMIT @limit RETURN { \"city\": group0, \"count\": groupCount } ","bindVars":{"@collection":"People","filterVar2":18,"limit":100}}"#,
+            ),
+            (
+                query2,
+                r#"{"query":"FOR item IN @@collection COLLECT group0 = item.city, group1 = item.age AGGREGATE aggregate2 = COUNT(item.name), aggregate3 = UNIQUE(item.name) INTO groupItems LIMIT @limit RETURN { \"city\": group0, \"age\": group1, \"count_name\": aggregate2, \"unique_name\": aggregate3, \"groups\": groupItems } ","bindVars":{"@collection":"People","limit":10}}"#,
+            ),
+            (
+                query3,
+                r#"{"query":"FOR item IN @@collection COLLECT AGGREGATE aggregate0 = AVG(item.age), aggregate1 = MAX(item.age) LIMIT @limit RETURN { \"avg_age\": aggregate0, \"max_age\": aggregate1 } ","bindVars":{"@collection":"People","limit":100}}"#,
+            ),
+            (
+                query4,
+                r#"{"query":"FOR item IN @@collection FILTER item.age > @filterVar2 COLLECT WITH COUNT INTO groupCount LIMIT @limit RETURN { \"count\": groupCount } ","bindVars":{"@collection":"People","filterVar2":18,"limit":100}}"#,
+            ),
+        ];
+
+        for (query, expected) in values {
+            assert_eq!(expected, serde_json::to_string(&query).unwrap());
+        }
+        // a bare COLLECT of no field would not be valid AQL
+        let empty = std::panic::catch_unwind(|| {
+            Person::query_builder(collection_name).read().group_by(&[])
+        });
+        assert!(empty.is_err());
+
+        let response: ArangoResponse<CityGroup> =
+            serde_json::from_str(r#"{"result":[{"city":"Liverpool","count":4}],"code":201}"#)
+                .unwrap();
+        assert_eq!(vec![CityGroup { city: String::from("Liverpool"), count: 4 }], response.result);
+    }
+
+    #[test]
+    fn test_keyset_token_encoding() {
+        let token = KeysetToken::new(serde_json::json!("John Lennon"), "1234");
+        let encoded = token.encode();
+        assert_eq!("eyJ2IjoiSm9obiBMZW5ub24iLCJrIjoiMTIzNCJ9", encoded);
+        assert_eq!(Some(token), KeysetToken::decode(&encoded));
+
+        assert_eq!(None, KeysetToken::decode("not a token"));
+        assert_eq!(None, KeysetToken::decode("eyJ2Ijo0Mn0"));
+    }
+
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    struct U {
+        _key: String,
+        _id: String,
+        _rev: String,
+        name: String,
+    }
+
+    impl U {
+        pub fn new(_key: &str, _id: &str, _rev: &str, name: &str) -> Self {
+            Self {
+                _key: String::from(_key),
+                _id: String::from(_id),
+                _rev: String::from(_rev),
+                name: String::from(name),
+            }
+        }
+    }
+
+    fn test_response() -> ArangoResponse<U> {
+        ArangoResponse::new(
+            vec![U::new("13221", "Characters/13221", "_ZEJkt1W---", "John Doe")],
+            false,
+            false,
+            ResponseExtra::new(0, 0, 0, 0, 0, 0, 3.654_956_817_626_953e-4, 2, 2107, vec![]),
+            false,
+            201,
+            String::new(),
+            0,
+            String::default(),
+        )
+    }
+
+    fn test_response_json() -> &'static str {
+        r#"{"result":[{"_key":"13221","_id":"Characters/13221","_rev":"_ZEJkt1W---","name":"John Doe"}],"hasMore":false,"cached":false,"extra":{"stats":{"writesExecuted":0,"writesIgnored":0,"scannedFull":0,"scannedIndex":0,"filtered":0,"httpRequests":0,"executionTime":0.0003654956817626953,"fullCount":2,"peakMemoryUsage":2107},"warnings":[]},"error":false,"code":201}"#
+    }
+
+    #[test]
+    fn test_arango_response_ser_deser() {
+        assert_eq!(test_response_json(), serde_json::to_string(&test_response()).unwrap());
+
+        assert_eq!(
+            test_response(),
+            serde_json::from_str(&serde_json::to_string(&test_response()).unwrap()).unwrap()
+        );
+
+        assert_eq!(
+            test_response(),
+            serde_json::from_slice(&serde_json::to_vec(&test_response()).unwrap()).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_arango_mock() {
+        let query = || Collection::new("Characters", CollectionType::Document).get_by_key("13221");
+        let query_json = || serde_json::to_string(&query()).unwrap();
+        let test_mock = ArangoMock::new(hashmap![query_json() => test_response_json().to_owned()]);
+        assert_eq!(test_response_json(), &test_mock.execute_query(query()));
+    }
+
+    #[cfg(feature = "actixclient")]
+    #[test]
+    fn test_arango_connection() {
+        use std::sync::Arc;
+        let aconn = ArangoConnection {
+            host: Arc::new("http://localhost:8529/_api/cursor".to_owned()),
+            client: Arc::new(actix_web::client::Client::new()),
+        };
+        let query = Collection::new("Characters", CollectionType::Document).get_by_key("13221");
+        // let real_res: Result<ArangoResponse<TestUser>, actix_web::Error> = query.exec(&aconn);
+        // assert!(real_res.is_err());
+
+        let client = actix_web::client::Client::new();
+        let aconn = ArangoConnection::new("http://localhost:8529/_api/cursor".to_owned(), client);
+        let query = Collection::new("Characters", CollectionType::Document).get_by_key("13221");
+        // let real_res: Result<ArangoResponse<TestUser>, actix_web::Error> = query.exec(&aconn);
+        // assert!(real_res.is_err());
+    }
+
+    /// a test for "missing field `hasMore` at line 1 column 85"
+    #[test]
+    fn test_arangoresponse() {
+        let responses = vec![
+            r#"{"code":400,"error":true,"errorMessage":"expecting POST /_api/cursor","errorNum":400}"#,
+        ];
+        for r in responses {
+            let resp: Result<ArangoResponse<String>, serde_json::error::Error> =
+                serde_json::from_str(r);
+            assert!(resp.is_ok());
+        }
+    }
+
+    #[test]
+    fn test_collection_mandatory() {
+        let key = "1234";
+
+        assert_eq!(
+            CollectionMandatory {
+                _key: String::from(key),
+                _id: String::default(),
+                _rev: String::default(),
+                _old_rev: String::default(),
+                extra: HashMap::new(),
+            },
             CollectionMandatory::with_key(key)
         );
     }
@@ -411,6 +1239,7 @@ mod tests {
         );
     }
 
+    #[cfg(feature = "reqwest")]
     #[actix_rt::test]
     async fn test_paging() {
         let url = || mockito::server_url();
@@ -469,4 +1298,998 @@ mod tests {
 
         mock_cursor_next.assert();
     }
+
+    #[cfg(feature = "reqwest")]
+    #[actix_rt::test]
+    async fn test_import() {
+        let db = Database {
+            name: "evt_test".to_string(),
+            connection: ArangoConnection::new(
+                mockito::server_url(),
+                "evt_test".to_string(),
+                Client::default(),
+            ),
+        };
+        let summary = |created| {
+            format!(
+                r#"{{"error":false,"created":{created},"errors":0,"empty":0,"updated":0,"ignored":0}}"#
+            )
+        };
+        let path = |overwrite| {
+            Matcher::Exact(format!(
+                "/_db/evt_test/_api/import?collection=Beatles&type=documents&onDuplicate=replace&complete=false&details=false&overwrite={overwrite}"))
+        };
+
+        let first = mock("POST", path(true))
+            .match_body("{\"name\":\"John\"}\n{\"name\":\"Paul\"}\n")
+            .with_header("content-type", "application/json")
+            .with_body(summary(2))
+            .expect(1)
+            .create();
+        let second = mock("POST", path(false))
+            .match_body("{\"name\":\"George\"}\n")
+            .with_header("content-type", "application/json")
+            .with_body(summary(1))
+            .expect(1)
+            .create();
+
+        let users = vec!["John", "Paul", "George"].into_iter().map(TestUser::new);
+        let options = ImportOptions::default()
+            .import_type(ImportType::Documents)
+            .on_duplicate(OnDuplicate::Replace)
+            .overwrite(true)
+            .chunk_size(2);
+        let result = db.import("Beatles", users, &options).await.unwrap();
+
+        first.assert();
+        second.assert();
+        assert_eq!(ImportSummary { created: 3, ..ImportSummary::default() }, result);
+
+        // maps with non-string keys fail to serialize
+        let unserializable = vec![hashmap! { (1, 2) => 3 }];
+        let failed = db.import("Beatles", unserializable, &options).await;
+        assert!(matches!(failed, Err(ArangoError::Json(_))));
+    }
+
+    #[cfg(feature = "reqwest")]
+    #[actix_rt::test]
+    async fn test_export_and_import_jsonl() {
+        let conn =
+            ArangoConnection::new(mockito::server_url(), "evt_test".to_string(), Client::default());
+        let db = Database { name: "evt_test".to_string(), connection: conn.clone() };
+
+        let first = mock("POST", "/_db/evt_test/_api/cursor")
+            .match_body(Matcher::Json(serde_json::json!({
+                "query": "FOR item in @@collection RETURN item",
+                "bindVars": { "@collection": "Beatles" },
+                "batchSize": 2,
+            })))
+            .with_status(201)
+            .with_header("content-type", "application/json")
+            .with_body(r#"{"result":[{"name":"John"},{"name":"Paul"}],"hasMore":true,"id":"42"}"#)
+            .expect(1)
+            .create();
+        let second = mock("PUT", "/_db/evt_test/_api/cursor/42")
+            .with_header("content-type", "application/json")
+            .with_body(r#"{"result":[{"name":"George"}],"hasMore":false}"#)
+            .expect(1)
+            .create();
+
+        let mut dump = vec![];
+        let count = test_collection().export(&conn, None, &mut dump, 2).await.unwrap();
+
+        first.assert();
+        second.assert();
+        assert_eq!(3, count);
+        let jsonl = "{\"name\":\"John\"}\n{\"name\":\"Paul\"}\n{\"name\":\"George\"}\n";
+        assert_eq!(jsonl, String::from_utf8(dump).unwrap());
+
+        let import = mock("POST", Matcher::Regex("^/_db/evt_test/_api/import\\?".to_string()))
+            .match_body(jsonl)
+            .with_header("content-type", "application/json")
+            .with_body(
+                r#"{"error":false,"created":3,"errors":0,"empty":0,"updated":0,"ignored":0}"#,
+            )
+            .expect(1)
+            .create();
+
+        let reader = futures_util::io::Cursor::new(format!("{jsonl}\n"));
+        let summary = db.import_jsonl("Beatles", reader, &ImportOptions::default()).await.unwrap();
+
+        import.assert();
+        assert_eq!(3, summary.created);
+
+        // the chunk before the invalid line is imported, the one of the line is not
+        let ringo = mock("POST", Matcher::Regex("^/_db/evt_test/_api/import\\?".to_string()))
+            .match_body("{\"name\": \"Ringo\"}\n{\"name\":\"Pete\"}\n")
+            .with_header("content-type", "application/json")
+            .with_body(
+                r#"{"error":false,"created":2,"errors":0,"empty":0,"updated":0,"ignored":0}"#,
+            )
+            .expect(1)
+            .create();
+        let reader = futures_util::io::Cursor::new(
+            "{\"name\": \"Ringo\"}\n\n{\"name\":\"Pete\"}\n{\"name\":\"George\"}\nnot json\n",
+        );
+        let options = ImportOptions::default().chunk_size(2);
+        let failed = db.import_jsonl("Beatles", reader, &options).await;
+        ringo.assert();
+        match failed {
+            Err(DumpError::Line { line, imported, source }) => {
+                assert_eq!((5, 2), (line, imported.created));
+                assert!(matches!(*source, DumpError::Json(_)));
+            }
+            _ => panic!("expected an invalid line"),
+        }
+    }
+
+    #[actix_rt::test]
+    async fn test_export_derived_filter() {
+        #[derive(ArangoBuilder, Serialize)]
+        pub struct Member {
+            name: String,
+            active: bool,
+        }
+
+        // 150 active members, read in batches of 100, up to the bound limit if any
+        let transport = std::sync::Arc::new(MemoryTransport::new(|request| {
+            let members = |range: std::ops::Range<u64>| {
+                range.map(|i| serde_json::json!({ "name": i, "active": true })).collect::<Vec<_>>()
+            };
+            if request.method == Method::Put {
+                let result = members(100..150);
+                return TransportResponse::new(
+                    200,
+                    serde_json::json!({ "result": result }).to_string(),
+                );
+            }
+            let body: serde_json::Value = serde_json::from_slice(&request.body).unwrap();
+            let limit = body["bindVars"]["limit"].as_u64().unwrap_or(150);
+            let result = members(0..limit.min(100));
+            let has_more = limit > 100;
+            let body = serde_json::json!({ "result": result, "hasMore": has_more, "id": "7" });
+            TransportResponse::new(201, body.to_string())
+        }));
+        let conn = ArangoConnection::with_transport(
+            "http://arango".to_string(),
+            "evt_test".to_string(),
+            transport.clone(),
+            Context::default(),
+        );
+        let active = || Member::query_builder("Members").read().filter().active_eq(&true);
+
+        let mut dump = vec![];
+        let filter = Some(active().unlimited().build());
+        let count = test_collection().export(&conn, filter, &mut dump, 100).await.unwrap();
+        assert_eq!(150, count);
+        assert_eq!(150, String::from_utf8(dump).unwrap().lines().count());
+        let first: serde_json::Value =
+            serde_json::from_slice(&transport.requests()[0].body).unwrap();
+        assert_eq!(
+            "FOR item IN @@collection FILTER item.active == @filterVar2 RETURN item ",
+            first["query"]
+        );
+
+        let filter = Some(active().build());
+        let count = test_collection().export(&conn, filter, &mut vec![], 100).await.unwrap();
+        assert_eq!(100, count);
+
+        // the filters after unlimited bind their values by new names
+        let filter = active().unlimited().and().name_eq(&String::from("7")).build();
+        assert_eq!(
+            r#"{"query":"FOR item IN @@collection FILTER item.active == @filterVar2 AND item.name == @filterVar3 RETURN item ","bindVars":{"@collection":"Members","filterVar2":true,"filterVar3":"7"}}"#,
+            serde_json::to_string(&filter).unwrap()
+        );
+    }
+
+    #[actix_rt::test]
+    async fn test_failed_export_deletes_cursor() {
+        let transport = std::sync::Arc::new(MemoryTransport::new(|request| match request.method {
+            Method::Post => TransportResponse::new(
+                201,
+                r#"{"result":[{"name":"John"}],"hasMore":true,"id":"42","error":false,"code":201}"#,
+            ),
+            Method::Put => TransportResponse::new(200, "not json"),
+            _ => TransportResponse::new(202, r#"{"id":"42","error":false,"code":202}"#),
+        }));
+        let conn = ArangoConnection::with_transport(
+            "http://arango".to_string(),
+            "evt_test".to_string(),
+            transport.clone(),
+            Context::default(),
+        );
+
+        let mut dump = vec![];
+        let failed = test_collection().export(&conn, None, &mut dump, 1).await;
+        assert!(matches!(failed, Err(DumpError::Request(ArangoError::Json(_)))));
+
+        let sent: Vec<_> =
+            transport.requests().into_iter().map(|request| (request.method, request.url)).collect();
+        let url = |path: &str| format!("http://arango/_db/evt_test/_api/cursor{path}");
+        assert_eq!(
+            vec![(Method::Post, url("")), (Method::Put, url("/42")), (Method::Delete, url("/42"))],
+            sent
+        );
+    }
+
+    #[cfg(feature = "blocking")]
+    #[test]
+    fn test_blocking_paging() {
+        let conn = crate::blocking::ArangoConnection::new(
+            mockito::server_url(),
+            "evt_test".to_string(),
+            reqwest::blocking::Client::new(),
+        );
+
+        let first = mock("POST", "/_db/evt_test/_api/cursor")
+            .with_status(201)
+            .with_header("content-type", "application/json")
+            .with_body(r#"{"result":[{"name":"John"}],"hasMore":true,"id":"42"}"#)
+            .expect(1)
+            .create();
+        let second = mock("PUT", "/_db/evt_test/_api/cursor/42")
+            .with_header("content-type", "application/json")
+            .with_body(r#"{"result":[{"name":"Paul"}],"hasMore":false}"#)
+            .expect(1)
+            .create();
+
+        let response = test_collection()
+            .get_all()
+            .into_batched(1)
+            .try_exec_blocking::<TestUser>(&conn)
+            .unwrap();
+        assert!(response.has_more);
+        assert_eq!("John", response.result[0].name);
+
+        let response = CursorExtractor(response.id).next_blocking::<TestUser>(&conn).unwrap();
+        assert!(!response.has_more);
+        assert_eq!("Paul", response.result[0].name);
+
+        first.assert();
+        second.assert();
+    }
+
+    #[cfg(feature = "blocking")]
+    #[test]
+    fn test_blocking_connection() {
+        let transport = std::sync::Arc::new(MemoryTransport::new(|request| match request.method {
+            Method::Get => TransportResponse::new(
+                403,
+                r#"{"error":true,"code":403,"errorNum":11,"errorMessage":"forbidden"}"#,
+            ),
+            _ => TransportResponse::new(
+                201,
+                r#"{"result":[{"name":"John"}],"hasMore":false,"error":false,"code":201}"#,
+            ),
+        }));
+        let conn = ArangoConnection::with_transport(
+            "http://arango".to_string(),
+            "evt_test".to_string(),
+            transport.clone(),
+            Context::default(),
+        )
+        .with_result_cache(ResultCache::default());
+        let conn = crate::blocking::ArangoConnection::from(conn);
+
+        // the blocking connection shares the stack of the async one, e.g. its result cache
+        for _ in 0..2 {
+            let read = test_collection().get_all().try_exec_blocking::<TestUser>(&conn).unwrap();
+            assert_eq!("John", read.result[0].name);
+        }
+        assert_eq!(1, transport.requests().len());
+
+        let db = crate::blocking::Database { name: "evt_test".to_string(), connection: conn };
+        assert!(matches!(db.list_collections(), Err(ArangoError::Server(_))));
+        assert!(matches!(
+            db.run(|db| async move { db.current_queries().await }),
+            Err(ArangoError::Server(_))
+        ));
+    }
+
+    #[actix_rt::test]
+    async fn test_memory_transport() {
+        let transport = std::sync::Arc::new(MemoryTransport::new(|request| {
+            if request.method == Method::Post {
+                TransportResponse::new(
+                    201,
+                    r#"{"result":[{"name":"John"}],"hasMore":true,"id":"7"}"#,
+                )
+            } else {
+                TransportResponse::new(200, r#"{"result":[{"name":"Paul"}]}"#)
+            }
+        }));
+        let conn = ArangoConnection::with_transport(
+            "http://arango".to_string(),
+            "evt_test".to_string(),
+            transport.clone(),
+            Context::default(),
+        );
+
+        let query = test_collection().get_all().into_batched(1);
+        let first = query.try_exec::<TestUser>(&conn).await.unwrap();
+        let second = CursorExtractor(first.id).next::<TestUser>(&conn).await.unwrap();
+        assert_eq!("John", first.result[0].name);
+        assert_eq!("Paul", second.result[0].name);
+
+        let requests = transport.requests();
+        assert_eq!(2, requests.len());
+        assert_eq!("http://arango/_db/evt_test/_api/cursor", requests[0].url);
+        assert_eq!(serde_json::to_vec(&query).unwrap(), requests[0].body);
+        assert!(requests[0].headers.iter().any(|(name, _)| name == "authorization"));
+        assert_eq!(Method::Put, requests[1].method);
+        assert_eq!("http://arango/_db/evt_test/_api/cursor/7", requests[1].url);
+
+        let transport = std::sync::Arc::new(MemoryTransport::new(|_| {
+            TransportResponse::new(502, "<html>Bad Gateway</html>")
+        }));
+        let conn = ArangoConnection::with_transport(
+            "http://arango".to_string(),
+            "evt_test".to_string(),
+            transport,
+            Context::default(),
+        );
+        let failed = test_collection().get_all().try_exec::<TestUser>(&conn).await;
+        assert!(matches!(failed, Err(ArangoError::Json(_))));
+    }
+
+    /// Query text, status, execution time and result count of a request.
+    type Seen = (String, Option<u16>, Option<f64>, Option<usize>);
+
+    #[derive(Default)]
+    struct Recorder {
+        seen: std::sync::Mutex<Vec<Seen>>,
+    }
+
+    impl Middleware for std::sync::Arc<Recorder> {
+        fn before_send(&self, request: &mut TransportRequest, _query: Option<&ArangoQuery>) {
+            request.headers.push(("x-correlation-id".to_owned(), "42".to_owned()));
+        }
+
+        fn after_receive(&self, exchange: &Exchange) {
+            let text = exchange.query.map(|query| query.query().to_owned()).unwrap_or_default();
+            let execution_time = exchange.stats().map(|stats| stats.execution_time);
+            self.seen.lock().unwrap().push((
+                text,
+                exchange.status(),
+                execution_time,
+                exchange.result_count(),
+            ));
+        }
+    }
+
+    #[actix_rt::test]
+    async fn test_middleware() {
+        let transport = std::sync::Arc::new(MemoryTransport::new(|request| {
+            if request.method == Method::Post {
+                TransportResponse::new(
+                    201,
+                    r#"{"result":[{"name":"John"},{"name":"Paul"}],"hasMore":true,"id":"7","extra":{"stats":{"executionTime":0.5}}}"#,
+                )
+            } else {
+                TransportResponse::new(404, r#"{"error":true,"code":404}"#)
+            }
+        }));
+        let recorder = std::sync::Arc::new(Recorder::default());
+        let conn = ArangoConnection::with_transport(
+            "http://arango".to_string(),
+            "evt_test".to_string(),
+            transport.clone(),
+            Context::default(),
+        )
+        .with_middleware(recorder.clone());
+
+        let first = test_collection().get_all().try_exec::<TestUser>(&conn).await.unwrap();
+        let _ = CursorExtractor(first.id).next::<TestUser>(&conn).await.unwrap();
+
+        assert!(transport.requests().iter().all(|request| request
+            .headers
+            .contains(&("x-correlation-id".to_owned(), "42".to_owned()))));
+        assert_eq!(
+            vec![
+                ("FOR item in @@collection RETURN item".to_owned(), Some(201), Some(0.5), Some(2)),
+                (String::new(), Some(404), None, None),
+            ],
+            *recorder.seen.lock().unwrap()
+        );
+    }
+
+    #[actix_rt::test]
+    async fn test_metrics() {
+        let transport = std::sync::Arc::new(MemoryTransport::new(|request| {
+            if request.method == Method::Post {
+                TransportResponse::new(
+                    201,
+                    r#"{"result":[],"extra":{"stats":{"executionTime":0.25,"scannedFull":10,"scannedIndex":2,"writesExecuted":1}}}"#,
+                )
+            } else {
+                TransportResponse::new(404, r#"{"error":true,"code":404,"errorNum":1600}"#)
+            }
+        }));
+        let recorder = std::sync::Arc::new(MemoryRecorder::new());
+        let conn = ArangoConnection::with_transport(
+            "http://arango".to_string(),
+            "evt_test".to_string(),
+            transport,
+            Context::default(),
+        )
+        .with_metrics(recorder.clone());
+
+        let query = test_collection().insert(&TestUser::new("Ringo")).with_label("add_user");
+        let _ = query.try_exec::<TestUser>(&conn).await.unwrap();
+        let _ = CursorExtractor("7".to_owned()).next::<TestUser>(&conn).await.unwrap();
+
+        let add_user = ("query", "add_user");
+        assert_eq!(1, recorder.histogram(REQUEST_DURATION, &[add_user]).len());
+        assert_eq!(vec![0.25], recorder.histogram(EXECUTION_TIME, &[add_user]));
+        assert_eq!(10, recorder.counter(DOCUMENTS_SCANNED, &[add_user, ("scan", "full")]));
+        assert_eq!(2, recorder.counter(DOCUMENTS_SCANNED, &[add_user, ("scan", "index")]));
+        assert_eq!(1, recorder.counter(WRITES, &[add_user, ("result", "executed")]));
+        assert_eq!(0, recorder.counter(ERRORS, &[add_user, ("code", "1600")]));
+        assert_eq!(1, recorder.counter(ERRORS, &[("query", "cursor"), ("code", "1600")]));
+        assert!(recorder.histogram(EXECUTION_TIME, &[("query", "cursor")]).is_empty());
+    }
+
+    #[actix_rt::test]
+    async fn test_retry() {
+        use std::sync::atomic::{AtomicUsize, Ordering};
+
+        // fails every request but the third one
+        let transport = std::sync::Arc::new(MemoryTransport::new({
+            let calls = AtomicUsize::new(0);
+            move |_| match calls.fetch_add(1, Ordering::SeqCst) {
+                2 => TransportResponse::new(201, r#"{"result":[{"name":"John"}]}"#),
+                1 => TransportResponse::new(500, r#"{"error":true,"code":500,"errorNum":18}"#),
+                _ => TransportResponse::new(503, r#"{"error":true,"code":503}"#),
+            }
+        }));
+        let conn = ArangoConnection::with_transport(
+            "http://arango".to_string(),
+            "evt_test".to_string(),
+            transport.clone(),
+            Context::default(),
+        )
+        .with_retry(RetryPolicy::default().max_attempts(3));
+
+        let read = test_collection().get_all().try_exec::<TestUser>(&conn).await.unwrap();
+        assert_eq!("John", read.result[0].name);
+        assert_eq!(3, transport.requests().len());
+
+        let write = test_collection().insert(&TestUser::new("Ringo"));
+        let failed = write.clone().try_exec::<TestUser>(&conn).await.unwrap();
+        assert_eq!(503, failed.code);
+        assert_eq!(4, transport.requests().len());
+
+        let failed = write.retry(true).try_exec::<TestUser>(&conn).await.unwrap();
+        assert_eq!(503, failed.code);
+        assert_eq!(7, transport.requests().len());
+
+        let failed = test_collection().get_all().retry(false).try_exec::<TestUser>(&conn).await;
+        assert_eq!(503, failed.unwrap().code);
+        assert_eq!(8, transport.requests().len());
+    }
+
+    #[test]
+    fn test_read_only_detection() {
+        assert!(is_read_only("FOR item in @@collection FILTER item.updated > @since RETURN item"));
+        assert!(!is_read_only("FOR item in @@collection remove item IN @@collection"));
+        assert!(!is_read_only("UPSERT { _key: @key } INSERT @doc UPDATE @doc IN @@collection"));
+    }
+
+    #[actix_rt::test]
+    async fn test_endpoints() {
+        let transport = std::sync::Arc::new(MemoryTransport::fallible(|request| {
+            let url = request.url.as_str();
+            if url.starts_with("http://a") {
+                Err(ArangoError::Connect("connection refused".into()))
+            } else if url.ends_with("/_api/cluster/endpoints") {
+                Ok(TransportResponse::new(
+                    200,
+                    r#"{"endpoints":[{"endpoint":"tcp://c:8529"},{"endpoint":"ssl://d:8529"}]}"#,
+                ))
+            } else if url.ends_with("/_api/cursor") {
+                Ok(TransportResponse::new(201, r#"{"result":[],"hasMore":true,"id":"7"}"#))
+            } else {
+                Ok(TransportResponse::new(200, r#"{"result":[],"hasMore":false,"id":"7"}"#))
+            }
+        }));
+        let hosts = vec!["http://a".to_owned(), "http://b".to_owned(), "http://c".to_owned()];
+        let conn = ArangoConnection::with_transport(
+            "http://arango".to_string(),
+            "evt_test".to_string(),
+            transport.clone(),
+            Context::default(),
+        )
+        .with_endpoints(Endpoints::new(hosts));
+
+        let first = test_collection().get_all().try_exec::<TestUser>(&conn).await.unwrap();
+        let _ = CursorExtractor(first.id).next::<TestUser>(&conn).await.unwrap();
+        // the refused coordinator is skipped from now on
+        let _ = test_collection().get_all().try_exec::<TestUser>(&conn).await.unwrap();
+
+        let urls: Vec<String> =
+            transport.requests().into_iter().map(|request| request.url).collect();
+        assert_eq!(
+            vec![
+                "http://a/_db/evt_test/_api/cursor",
+                "http://b/_db/evt_test/_api/cursor",
+                "http://b/_db/evt_test/_api/cursor/7",
+                "http://c/_db/evt_test/_api/cursor",
+            ],
+            urls
+        );
+
+        let hosts = conn.refresh_endpoints().await.unwrap();
+        assert_eq!(vec!["http://c:8529".to_owned(), "https://d:8529".to_owned()], hosts);
+        assert_eq!(hosts, conn.endpoints.as_ref().unwrap().hosts());
+    }
+
+    #[actix_rt::test]
+    async fn test_cursor_pins() {
+        use std::sync::atomic::{AtomicUsize, Ordering};
+        use std::time::Duration;
+
+        let transport = std::sync::Arc::new(MemoryTransport::new({
+            let created = AtomicUsize::new(0);
+            move |request| match request.method {
+                Method::Post => {
+                    let id = created.fetch_add(1, Ordering::SeqCst) + 1;
+                    let body = format!(r#"{{"result":[],"hasMore":true,"id":"{id}"}}"#);
+                    TransportResponse::new(201, body)
+                }
+                _ if request.url.ends_with("/1") => TransportResponse::new(
+                    404,
+                    r#"{"error":true,"code":404,"errorNum":1600,"errorMessage":"cursor not found"}"#,
+                ),
+                _ => TransportResponse::new(200, r#"{"result":[],"hasMore":true,"id":"2"}"#),
+            }
+        }));
+        let connect = |endpoints: Endpoints| {
+            ArangoConnection::with_transport(
+                "http://arango".to_string(),
+                "evt_test".to_string(),
+                transport.clone(),
+                Context::default(),
+            )
+            .with_endpoints(endpoints)
+        };
+        let hosts = || vec!["http://a".to_owned(), "http://b".to_owned()];
+        let conn = connect(Endpoints::new(hosts()));
+
+        // a failed continuation unpins its cursor
+        let first = test_collection().get_all().try_exec::<TestUser>(&conn).await.unwrap();
+        let _ = CursorExtractor(first.id.clone()).next::<TestUser>(&conn).await.unwrap();
+        let _ = CursorExtractor(first.id).next::<TestUser>(&conn).await.unwrap();
+        // so does removing its coordinator
+        let second = test_collection().get_all().try_exec::<TestUser>(&conn).await.unwrap();
+        let _ = CursorExtractor(second.id.clone()).next::<TestUser>(&conn).await.unwrap();
+        conn.endpoints.as_ref().unwrap().set_hosts(vec!["http://b".to_owned()]);
+        let _ = CursorExtractor(second.id).next::<TestUser>(&conn).await.unwrap();
+        // and not using it for the cursor TTL
+        let conn = connect(Endpoints::new(hosts()).cursor_ttl(Duration::from_secs(0)));
+        let third = test_collection().get_all().try_exec::<TestUser>(&conn).await.unwrap();
+        let _ = CursorExtractor(third.id).next::<TestUser>(&conn).await.unwrap();
+
+        let urls: Vec<String> =
+            transport.requests().into_iter().map(|request| request.url).collect();
+        assert_eq!(
+            vec![
+                "http://a/_db/evt_test/_api/cursor",
+                "http://a/_db/evt_test/_api/cursor/1",
+                "http://b/_db/evt_test/_api/cursor/1",
+                "http://a/_db/evt_test/_api/cursor",
+                "http://a/_db/evt_test/_api/cursor/2",
+                "http://b/_db/evt_test/_api/cursor/2",
+                "http://a/_db/evt_test/_api/cursor",
+                "http://b/_db/evt_test/_api/cursor/3",
+            ],
+            urls
+        );
+    }
+
+    #[cfg(feature = "reqwest")]
+    #[actix_rt::test]
+    async fn test_active_failover() {
+        let leader = mockito::server_url();
+        let address = leader.trim_start_matches("http://").to_owned();
+        let port = address.rsplit(':').next().unwrap().to_owned();
+        let follower = format!("http://localhost:{port}");
+        let conn =
+            ArangoConnection::new(follower.clone(), "failover_test".to_string(), Client::default());
+
+        let from_follower = mock("POST", "/_db/failover_test/_api/cursor")
+            .match_header("host", format!("localhost:{port}").as_str())
+            .with_status(503)
+            .with_header("x-arango-endpoint", &format!("tcp://{address}"))
+            .with_body(r#"{"error":true,"code":503,"errorNum":1496,"errorMessage":"not a leader"}"#)
+            .expect(1)
+            .create();
+        let from_leader = mock("POST", "/_db/failover_test/_api/cursor")
+            .match_header("host", address.as_str())
+            .with_status(201)
+            .with_header("content-type", "application/json")
+            .with_body(test_response_json())
+            .expect(2)
+            .create();
+
+        let query = || Collection::new("Characters", CollectionType::Document).get_by_key("13221");
+        let first = query().try_exec::<TestUser>(&conn).await.unwrap();
+        assert_eq!(leader, conn.active_host());
+        // the leader stays active
+        let second = query().try_exec::<TestUser>(&conn).await.unwrap();
+
+        from_follower.assert();
+        from_leader.assert();
+        assert_eq!((1, 1), (first.result.len(), second.result.len()));
+        assert_eq!(follower, *conn.host);
+    }
+
+    #[actix_rt::test]
+    async fn test_timeout() {
+        use std::time::Duration;
+
+        let transport = std::sync::Arc::new(MemoryTransport::fallible(|request| {
+            match request.method {
+                Method::Post => Ok(TransportResponse::new(
+                    201,
+                    r#"{"result":[{"name":"John"}],"hasMore":true,"id":"9","error":false,"code":201}"#,
+                )),
+                Method::Put => Err(ArangoError::Timeout("operation timed out".into())),
+                _ => Ok(TransportResponse::new(202, r#"{"id":"9","error":false,"code":202}"#)),
+            }
+        }));
+        let conn = ArangoConnection::with_transport(
+            "http://arango".to_string(),
+            "evt_test".to_string(),
+            transport.clone(),
+            Context::default(),
+        )
+        .with_timeout(Duration::from_secs(2));
+
+        let first = test_collection().get_all().try_exec::<TestUser>(&conn).await.unwrap();
+        let query = test_collection().get_all().with_timeout(Duration::from_millis(500));
+        let _ = query.try_exec::<TestUser>(&conn).await.unwrap();
+        let next = CursorExtractor(first.id).next::<TestUser>(&conn).await;
+        assert!(matches!(next, Err(ArangoError::Timeout(_))));
+
+        let requests = transport.requests();
+        let options = |request: &TransportRequest| {
+            serde_json::from_slice::<serde_json::Value>(&request.body).unwrap()["options"].clone()
+        };
+        assert_eq!(serde_json::json!({ "maxRuntime": 2.0 }), options(&requests[0]));
+        assert_eq!(serde_json::json!({ "maxRuntime": 0.5 }), options(&requests[1]));
+        let sent: Vec<_> = requests
+            .iter()
+            .map(|request| (request.method, request.url.as_str(), request.timeout))
+            .collect();
+        assert_eq!(
+            vec![
+                (
+                    Method::Post,
+                    "http://arango/_db/evt_test/_api/cursor",
+                    Some(Duration::from_secs(2))
+                ),
+                (
+                    Method::Post,
+                    "http://arango/_db/evt_test/_api/cursor",
+                    Some(Duration::from_millis(500))
+                ),
+                (
+                    Method::Put,
+                    "http://arango/_db/evt_test/_api/cursor/9",
+                    Some(Duration::from_secs(2))
+                ),
+                (
+                    Method::Delete,
+                    "http://arango/_db/evt_test/_api/cursor/9",
+                    Some(Duration::from_secs(2))
+                ),
+            ],
+            sent
+        );
+    }
+
+    #[actix_rt::test]
+    async fn test_query_inspection() {
+        let tracking = r#"{"enabled":true,"trackSlowQueries":true,"trackBindVars":false,"maxSlowQueries":64,"slowQueryThreshold":10,"slowStreamingQueryThreshold":10,"maxQueryStringLength":4096}"#;
+        let transport = std::sync::Arc::new(MemoryTransport::new(move |request| {
+            match (request.method, request.url.rsplit('/').next().unwrap_or_default()) {
+                (Method::Get, "current") => TransportResponse::new(
+                    200,
+                    r#"[{"id":"68","database":"evt_test","user":"root","query":"FOR i IN 1..10000000 RETURN i","bindVars":{},"started":"2020-06-08T10:20:34Z","runTime":61.5,"peakMemoryUsage":32768,"state":"executing","stream":false}]"#,
+                ),
+                (Method::Get, "slow") => TransportResponse::new(200, "[]"),
+                (Method::Get, _) => TransportResponse::new(200, tracking),
+                (Method::Put, _) => TransportResponse::new(200, request.body.clone()),
+                (Method::Delete, "68" | "slow") => {
+                    TransportResponse::new(200, r#"{"error":false,"code":200}"#)
+                }
+                _ => TransportResponse::new(
+                    404,
+                    r#"{"error":true,"code":404,"errorNum":1591,"errorMessage":"query ID not found in query list"}"#,
+                ),
+            }
+        }));
+        let db = Database {
+            name: "evt_test".to_string(),
+            connection: ArangoConnection::with_transport(
+                "http://arango".to_string(),
+                "evt_test".to_string(),
+                transport.clone(),
+                Context::default(),
+            ),
+        };
+
+        let running = db.current_queries().await.unwrap();
+        assert_eq!(1, running.len());
+        assert_eq!(
+            ("68", 61.5, "executing"),
+            (running[0].id.as_str(), running[0].run_time, running[0].state.as_str())
+        );
+        assert!(db.slow_queries().await.unwrap().is_empty());
+
+        assert!(!db.kill_query(&running[0].id).await.unwrap().error);
+        let finished = db.kill_query("69").await.unwrap();
+        assert_eq!((true, 1591), (finished.error, finished.error_num));
+        assert_eq!(200, db.clear_slow_queries().await.unwrap().code);
+
+        let tracking = db.query_tracking().await.unwrap();
+        assert_eq!((true, 10.0), (tracking.track_slow_queries, tracking.slow_query_threshold));
+        let tracking =
+            QueryTracking { slow_query_threshold: 2.5, track_bind_vars: true, ..tracking };
+        assert_eq!(tracking, db.set_query_tracking(&tracking).await.unwrap());
+
+        let sent: Vec<_> =
+            transport.requests().into_iter().map(|request| (request.method, request.url)).collect();
+        let url = |path: &str| format!("http://arango/_db/evt_test/_api/query/{path}");
+        assert_eq!(
+            vec![
+                (Method::Get, url("current")),
+                (Method::Get, url("slow")),
+                (Method::Delete, url("68")),
+                (Method::Delete, url("69")),
+                (Method::Delete, url("slow")),
+                (Method::Get, url("properties")),
+                (Method::Put, url("properties")),
+            ],
+            sent
+        );
+
+        let forbidden = Database {
+            name: "evt_test".to_string(),
+            connection: ArangoConnection::with_transport(
+                "http://arango".to_string(),
+                "evt_test".to_string(),
+                std::sync::Arc::new(MemoryTransport::new(|_| {
+                    TransportResponse::new(
+                        403,
+                        r#"{"error":true,"code":403,"errorNum":11,"errorMessage":"forbidden"}"#,
+                    )
+                })),
+                Context::default(),
+            ),
+        };
+        match forbidden.current_queries().await {
+            Err(ArangoError::Server(status)) => {
+                assert_eq!(
+                    (403, 11, "forbidden"),
+                    (status.code, status.error_num, status.error_message.as_str())
+                );
+            }
+            other => panic!("expected a server error, got {:?}", other),
+        }
+        assert!(matches!(forbidden.query_tracking().await, Err(ArangoError::Server(_))));
+    }
+
+    #[actix_rt::test]
+    async fn test_query_cache() {
+        let transport = std::sync::Arc::new(MemoryTransport::new(|request| {
+            match (request.method, request.url.rsplit('/').next().unwrap_or_default()) {
+                (Method::Post, _) => TransportResponse::new(
+                    201,
+                    r#"{"result":[{"name":"John"}],"hasMore":false,"cached":true,"error":false,"code":201}"#,
+                ),
+                (Method::Get, "entries") => TransportResponse::new(
+                    200,
+                    r#"[{"hash":"5171352948283425914","query":"FOR u IN users RETURN u","bindVars":{},"size":120,"results":1,"hits":3,"runTime":0.02,"started":"2020-06-08T10:20:34Z","dataSources":["users"]}]"#,
+                ),
+                (Method::Get, _) => TransportResponse::new(
+                    200,
+                    r#"{"mode":"off","maxResults":128,"maxResultsSize":268435456,"maxEntrySize":16777216,"includeSystem":false}"#,
+                ),
+                (Method::Put, _) if request.body.starts_with(b"{\"mode\":\"on\"") => {
+                    TransportResponse::new(
+                        400,
+                        r#"{"error":true,"code":400,"errorNum":10,"errorMessage":"bad parameter"}"#,
+                    )
+                }
+                (Method::Put, _) => TransportResponse::new(200, request.body.clone()),
+                _ => TransportResponse::new(200, r#"{"error":false,"code":200}"#),
+            }
+        }));
+        let db = Database {
+            name: "evt_test".to_string(),
+            connection: ArangoConnection::with_transport(
+                "http://arango".to_string(),
+                "evt_test".to_string(),
+                transport.clone(),
+                Context::default(),
+            ),
+        };
+
+        let query = test_collection().get_all();
+        assert!(!serde_json::to_string(&query).unwrap().contains("cache"));
+        let read = query.cache(true).try_exec::<TestUser>(&db.connection).await.unwrap();
+        assert!(read.cached);
+
+        let properties = db.query_cache_properties().await.unwrap();
+        assert_eq!((QueryCacheMode::Off, 128), (properties.mode, properties.max_results));
+        let properties = QueryCacheProperties { mode: QueryCacheMode::Demand, ..properties };
+        assert_eq!(properties, db.set_query_cache_properties(&properties).await.unwrap());
+        let entries = db.query_cache_entries().await.unwrap();
+        assert_eq!(
+            (3, vec!["users".to_owned()]),
+            (entries[0].hits, entries[0].data_sources.clone())
+        );
+        assert!(!db.clear_query_cache().await.unwrap().error);
+        let refused = QueryCacheProperties { mode: QueryCacheMode::On, ..properties };
+        match db.set_query_cache_properties(&refused).await {
+            Err(ArangoError::Server(status)) => {
+                assert_eq!((400, 10), (status.code, status.error_num));
+            }
+            other => panic!("expected a server error, got {:?}", other),
+        }
+
+        let requests = transport.requests();
+        let body = |index: usize| {
+            serde_json::from_slice::<serde_json::Value>(&requests[index].body).unwrap()
+        };
+        assert_eq!(serde_json::json!(true), body(0)["cache"]);
+        assert_eq!(serde_json::json!("demand"), body(2)["mode"]);
+        let sent: Vec<_> =
+            requests.iter().map(|request| (request.method, request.url.as_str())).collect();
+        assert_eq!(
+            vec![
+                (Method::Post, "http://arango/_db/evt_test/_api/cursor"),
+                (Method::Get, "http://arango/_db/evt_test/_api/query-cache/properties"),
+                (Method::Put, "http://arango/_db/evt_test/_api/query-cache/properties"),
+                (Method::Get, "http://arango/_db/evt_test/_api/query-cache/entries"),
+                (Method::Delete, "http://arango/_db/evt_test/_api/query-cache"),
+                (Method::Put, "http://arango/_db/evt_test/_api/query-cache/properties"),
+            ],
+            sent
+        );
+    }
+
+    #[actix_rt::test]
+    async fn test_result_cache() {
+        use crate::transport::Transport;
+        use futures_util::future::BoxFuture;
+        use std::sync::atomic::{AtomicBool, Ordering};
+
+        /// Invalidates `Stones` while a response is on its way, like a concurrent write.
+        struct ConcurrentWrite(std::sync::Arc<ResultCache>);
+
+        impl Middleware for ConcurrentWrite {
+            fn after_receive(&self, _exchange: &Exchange) {
+                self.0.invalidate("Stones");
+            }
+        }
+
+        /// Holds the responses of writes until `committed`.
+        struct SlowWrites {
+            transport: MemoryTransport,
+            committed: std::sync::Arc<AtomicBool>,
+        }
+
+        impl Transport for SlowWrites {
+            fn send(
+                &self,
+                request: TransportRequest,
+            ) -> BoxFuture<'static, Result<TransportResponse, ArangoError>> {
+                let write = String::from_utf8_lossy(&request.body).contains("INSERT");
+                let response = self.transport.send(request);
+                let committed = std::sync::Arc::clone(&self.committed);
+                Box::pin(async move {
+                    futures_util::future::poll_fn(|context| {
+                        if write && !committed.load(Ordering::SeqCst) {
+                            context.waker().wake_by_ref();
+                            return std::task::Poll::Pending;
+                        }
+                        std::task::Poll::Ready(())
+                    })
+                    .await;
+                    response.await
+                })
+            }
+
+            fn sleep(&self, duration: std::time::Duration) -> BoxFuture<'static, ()> {
+                self.transport.sleep(duration)
+            }
+        }
+
+        let committed = std::sync::Arc::new(AtomicBool::new(true));
+        let transport = std::sync::Arc::new(SlowWrites {
+            transport: MemoryTransport::new(|_| {
+                TransportResponse::new(
+                    201,
+                    r#"{"result":[{"name":"John"}],"hasMore":false,"error":false,"code":201}"#,
+                )
+            }),
+            committed: committed.clone(),
+        });
+        let recorder = std::sync::Arc::new(MemoryRecorder::new());
+        let conn = ArangoConnection::with_transport(
+            "http://arango".to_string(),
+            "evt_test".to_string(),
+            transport.clone(),
+            Context::default(),
+        )
+        .with_result_cache(ResultCache::default().max_entries(2))
+        .with_metrics(recorder.clone());
+        let cache = conn.result_cache.clone().unwrap();
+        let stones = || Collection::new("Stones", CollectionType::Document).get_by_key("Mick");
+
+        for _ in 0..2 {
+            let read = test_collection().get_all().try_exec::<TestUser>(&conn).await.unwrap();
+            assert_eq!("John", read.result[0].name);
+        }
+        let _ = stones().try_exec::<TestUser>(&conn).await.unwrap();
+        assert_eq!(2, transport.transport.requests().len());
+        assert_eq!(CacheStats { hits: 1, misses: 2, entries: 2 }, cache.stats());
+        assert_eq!(1, recorder.counter(CACHE_HITS, &[("query", "query")]));
+        assert_eq!(2, recorder.histogram(REQUEST_DURATION, &[("query", "query")]).len());
+
+        // a write invalidates the results of its collection only
+        let _ = test_collection().insert(&TestUser::new("Ringo")).try_exec::<TestUser>(&conn).await;
+        let _ = test_collection().get_all().try_exec::<TestUser>(&conn).await.unwrap();
+        let _ = stones().try_exec::<TestUser>(&conn).await.unwrap();
+        assert_eq!(4, transport.transport.requests().len());
+        assert_eq!(CacheStats { hits: 2, misses: 3, entries: 2 }, cache.stats());
+
+        // the oldest result is evicted when full
+        let _ = stones().into_batched(10).try_exec::<TestUser>(&conn).await;
+        assert_eq!(CacheStats { hits: 2, misses: 4, entries: 2 }, cache.stats());
+        let _ = stones().try_exec::<TestUser>(&conn).await.unwrap();
+        assert_eq!(6, transport.transport.requests().len());
+
+        // so does a write of the document API
+        let stones_collection = Collection::new("Stones", CollectionType::Document);
+        let _ = stones_collection.remove_documents::<TestUser, _>(&conn, &["Mick"]).await;
+        let _ = stones().try_exec::<TestUser>(&conn).await.unwrap();
+        assert_eq!(8, transport.transport.requests().len());
+
+        // a write without bound collections invalidates all results
+        let raw =
+            ArangoQuery::raw("INSERT { name: 'Keith' } INTO Stones".to_owned(), BTreeMap::new());
+        let _ = raw.try_exec::<TestUser>(&conn).await.unwrap();
+        assert_eq!(0, cache.stats().entries);
+
+        // a result read before a write is not cached after it
+        let conn = conn.with_middleware(ConcurrentWrite(cache.clone()));
+        let _ = stones().try_exec::<TestUser>(&conn).await.unwrap();
+        let _ = test_collection().get_all().try_exec::<TestUser>(&conn).await.unwrap();
+        assert_eq!(1, cache.stats().entries);
+        let _ = stones().try_exec::<TestUser>(&conn).await.unwrap();
+        assert_eq!(12, transport.transport.requests().len());
+
+        // a read which reaches the server before a write is not cached after the write finished
+        committed.store(false, Ordering::SeqCst);
+        let write = test_collection().insert(&TestUser::new("Ringo"));
+        let mut write = Box::pin(write.try_exec::<TestUser>(&conn));
+        assert!(futures_util::poll!(&mut write).is_pending());
+        let _ = test_collection().get_all().try_exec::<TestUser>(&conn).await.unwrap();
+        committed.store(true, Ordering::SeqCst);
+        let _ = write.await.unwrap();
+        let _ = test_collection().get_all().try_exec::<TestUser>(&conn).await.unwrap();
+        assert_eq!(15, transport.transport.requests().len());
+
+        // a read without bound collections is not cached, a bound write may change it
+        let raw_read =
+            || ArangoQuery::raw("FOR item IN Beatles RETURN item".to_owned(), BTreeMap::new());
+        let _ = raw_read().try_exec::<TestUser>(&conn).await.unwrap();
+        let write = test_collection().insert(&TestUser::new("George"));
+        let _ = write.try_exec::<TestUser>(&conn).await.unwrap();
+        let _ = raw_read().try_exec::<TestUser>(&conn).await.unwrap();
+        assert_eq!(18, transport.transport.requests().len());
+    }
 }
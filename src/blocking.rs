@@ -0,0 +1,215 @@
+//! Blocking counterparts of `ArangoConnection`, query execution and `Database`,
+//! for synchronous programs like CLI tools and build scripts.
+//! Queries, cursors and responses are the same types as in the async API.
+//! ```ignore
+//! use arangoq::blocking::ArangoConnection;
+//!
+//! let conn = ArangoConnection::new(host, db_name, reqwest::blocking::Client::new());
+//! let response = collection.get_all().try_exec_blocking::<User>(&conn)?;
+//! ```
+use crate::arango_api::{
+    ArangoQuery, Collection, CollectionType, CursorExtractor, ExecuteArangoQuery,
+};
+use crate::arango_connection::{self, Context};
+use crate::arango_response::ArangoResponse;
+use crate::database;
+use crate::error::ArangoError;
+use crate::transport::{Method, Transport, TransportRequest, TransportResponse};
+use futures_util::future::{self, BoxFuture};
+use futures_util::task::{waker, ArcWake};
+use reqwest::blocking::Client;
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use serde_json::Value;
+use std::future::Future;
+use std::sync::Arc;
+use std::task::Poll;
+use std::thread::{self, Thread};
+use std::time::Duration;
+
+/// Sends the request before returning the future, which is then ready.
+impl Transport for Client {
+    fn send(
+        &self,
+        request: TransportRequest,
+    ) -> BoxFuture<'static, Result<TransportResponse, ArangoError>> {
+        let mut builder = match request.method {
+            Method::Get => self.get(&request.url),
+            Method::Post => self.post(&request.url),
+            Method::Put => self.put(&request.url),
+            Method::Patch => self.patch(&request.url),
+            Method::Delete => self.delete(&request.url),
+        };
+        if !request.query.is_empty() {
+            builder = builder.query(&request.query);
+        }
+        for (name, value) in &request.headers {
+            builder = builder.header(name.as_str(), value.as_str());
+        }
+        if let Some(timeout) = request.timeout {
+            builder = builder.timeout(timeout);
+        }
+        let received = builder.body(request.body).send().and_then(|response| {
+            let status = response.status().as_u16();
+            let headers = response
+                .headers()
+                .iter()
+                .filter_map(|(name, value)| {
+                    Some((name.as_str().to_owned(), value.to_str().ok()?.to_owned()))
+                })
+                .collect();
+            let body = response.bytes()?.to_vec();
+            Ok(TransportResponse { status, headers, body })
+        });
+        Box::pin(future::ready(received.map_err(ArangoError::from)))
+    }
+
+    fn sleep(&self, duration: Duration) -> BoxFuture<'static, ()> {
+        thread::sleep(duration);
+        Box::pin(future::ready(()))
+    }
+}
+
+struct ThreadWaker(Thread);
+
+impl ArcWake for ThreadWaker {
+    fn wake_by_ref(arc_self: &Arc<Self>) {
+        arc_self.0.unpark();
+    }
+}
+
+/// Polls `future` on the calling thread, parking it while the future is pending.
+fn block_on<F: Future>(future: F) -> F::Output {
+    let mut future = Box::pin(future);
+    let waker = waker(Arc::new(ThreadWaker(thread::current())));
+    let mut context = std::task::Context::from_waker(&waker);
+    loop {
+        if let Poll::Ready(output) = future.as_mut().poll(&mut context) {
+            return output;
+        }
+        thread::park();
+    }
+}
+
+/// Same as `arangoq::ArangoConnection`, blocking the calling thread until the response.
+///
+/// Requests go through the wrapped async connection, with its middleware, retries, timeouts,
+/// endpoints, metrics and result cache. Its transport must not need an async runtime,
+/// like `reqwest::blocking::Client`. Must not be used from within an async runtime.
+/// ```ignore
+/// let transport = Arc::new(reqwest::blocking::Client::new());
+/// let conn = arangoq::ArangoConnection::with_transport(host, db_name, transport, Context::default())
+///     .with_retry(RetryPolicy::default());
+/// let conn = arangoq::blocking::ArangoConnection::from(conn);
+/// ```
+#[derive(Clone)]
+pub struct ArangoConnection {
+    pub connection: arango_connection::ArangoConnection,
+}
+
+impl From<arango_connection::ArangoConnection> for ArangoConnection {
+    fn from(connection: arango_connection::ArangoConnection) -> Self {
+        Self { connection }
+    }
+}
+
+impl ArangoConnection {
+    #[must_use]
+    pub fn new(host: String, database: String, client: Client) -> Self {
+        Self::with_context(host, database, client, Context::default())
+    }
+    #[must_use]
+    pub fn with_context(host: String, database: String, client: Client, context: Context) -> Self {
+        let transport = Arc::new(client);
+        arango_connection::ArangoConnection::with_transport(host, database, transport, context)
+            .into()
+    }
+}
+
+impl ArangoQuery {
+    /// Blocking version of `try_exec`.
+    /// # Errors
+    ///
+    /// Returns `ArangoError`
+    pub fn try_exec_blocking<T: Serialize + DeserializeOwned>(
+        &self,
+        dbc: &ArangoConnection,
+    ) -> Result<ArangoResponse<T>, ArangoError> {
+        block_on(self.try_exec(&dbc.connection))
+    }
+}
+
+impl CursorExtractor {
+    /// Blocking version of `next`.
+    /// # Errors
+    ///
+    /// Returns `ArangoError`
+    pub fn next_blocking<T: Serialize + DeserializeOwned>(
+        &self,
+        dbc: &ArangoConnection,
+    ) -> Result<ArangoResponse<T>, ArangoError> {
+        block_on(self.next(&dbc.connection))
+    }
+}
+
+impl ExecuteArangoQuery for ArangoConnection {
+    type Output = Result<ArangoResponse<Value>, ArangoError>;
+
+    fn execute_query(&self, query: ArangoQuery) -> Self::Output {
+        query.try_exec_blocking(self)
+    }
+}
+
+/// Same as `arangoq::Database`, with blocking calls.
+pub struct Database {
+    pub name: String,
+    pub connection: ArangoConnection,
+}
+
+impl Database {
+    /// Blocks on `call` with the async `Database`, to use any of its methods.
+    /// ```ignore
+    /// let slow = db.run(|db| async move { db.slow_queries().await })?;
+    /// ```
+    pub fn run<F: Future>(&self, call: impl FnOnce(database::Database) -> F) -> F::Output {
+        block_on(call(database::Database {
+            name: self.name.clone(),
+            connection: self.connection.connection.clone(),
+        }))
+    }
+
+    /// # Errors
+    ///
+    /// Returns `ArangoError`, `ArangoError::Server` if the server refuses the collection.
+    pub fn create_collection(
+        &self,
+        local_name: &str,
+        collection_type: CollectionType,
+    ) -> Result<(), ArangoError> {
+        let connection = &self.connection.connection;
+        let data = serde_json::json!({
+            "name": connection.context.collection_name(local_name),
+            "type": collection_type as u8
+        });
+        log::debug!("{data}");
+        let request = connection
+            .request(Method::Post, connection.collection())
+            .header("accept", "application/json")
+            .json(&data);
+        let res: Value = block_on(connection.fetch(request))?;
+        log::debug!("{res:#?}");
+        Ok(())
+    }
+
+    /// # Errors
+    ///
+    /// Returns `ArangoError`
+    pub fn list_collections(&self) -> Result<Vec<Collection>, ArangoError> {
+        let connection = &self.connection.connection;
+        let request = connection
+            .request(Method::Get, connection.collection())
+            .header("accept", "application/json");
+        let data: Value = block_on(connection.fetch(request))?;
+        Ok(serde_json::from_value(data["result"].clone()).unwrap_or_default())
+    }
+}
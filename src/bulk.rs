@@ -0,0 +1,119 @@
+use crate::arango_api::{Collection, OverwriteMode};
+use crate::arango_connection::ArangoConnection;
+use crate::arango_response::BulkResponse;
+use crate::error::ArangoError;
+use crate::transport::Method;
+use serde::de::DeserializeOwned;
+use serde::ser::Error;
+use serde::Serialize;
+use serde_json::Value;
+
+impl Collection {
+    /// Inserts `elems` with `POST /_api/document/{collection}`, with a result per document
+    /// in the order of `elems`. Unlike `InsertMany`, the documents which fail are always skipped
+    /// and reported with their error. `overwrite_mode` decides what happens to documents
+    /// with an existing `_key`, a unique constraint violation if `None`.
+    /// ```ignore
+    /// let inserted = coll.insert_documents::<TestUser, _>(&conn, &users, None).await?;
+    /// for failure in inserted.failures() {
+    ///     log::warn!("user {} not inserted: {}", failure.index, failure.error_message);
+    /// }
+    /// ```
+    /// # Errors
+    ///
+    /// Returns `ArangoError`, `ArangoError::Server` if the request fails as a whole,
+    /// e.g. on a missing collection.
+    pub async fn insert_documents<T: DeserializeOwned, Elem: Serialize>(
+        &self,
+        dbc: &ArangoConnection,
+        elems: &[Elem],
+        overwrite_mode: Option<OverwriteMode>,
+    ) -> Result<BulkResponse<T>, ArangoError> {
+        let mut params = return_new();
+        if let Some(overwrite_mode) = overwrite_mode {
+            let overwrite_mode = serde_json::to_value(overwrite_mode)?;
+            params.push(("overwriteMode", overwrite_mode.as_str().unwrap_or_default().to_owned()));
+        }
+        self.write_documents(dbc, Method::Post, &params, serde_json::to_vec(elems)?).await
+    }
+
+    /// Updates the documents of the keys of `updates` with `PATCH /_api/document/{collection}`,
+    /// with a result per document in the order of `updates`.
+    /// # Errors
+    ///
+    /// Returns `ArangoError`, `ArangoError::Json` as well if an update is not an object.
+    pub async fn update_documents<T: DeserializeOwned, Key: Serialize, Update: Serialize>(
+        &self,
+        dbc: &ArangoConnection,
+        updates: &[(Key, Update)],
+    ) -> Result<BulkResponse<T>, ArangoError> {
+        let body = serde_json::to_vec(&with_keys(updates)?)?;
+        self.write_documents(dbc, Method::Patch, &return_new(), body).await
+    }
+
+    /// Replaces the documents of the keys of `elems` with `PUT /_api/document/{collection}`,
+    /// with a result per document in the order of `elems`.
+    /// # Errors
+    ///
+    /// Returns `ArangoError`, `ArangoError::Json` as well if a document is not an object.
+    pub async fn replace_documents<T: DeserializeOwned, Key: Serialize, Elem: Serialize>(
+        &self,
+        dbc: &ArangoConnection,
+        elems: &[(Key, Elem)],
+    ) -> Result<BulkResponse<T>, ArangoError> {
+        let body = serde_json::to_vec(&with_keys(elems)?)?;
+        self.write_documents(dbc, Method::Put, &return_new(), body).await
+    }
+
+    /// Removes the documents of `keys` with `DELETE /_api/document/{collection}`,
+    /// with a result per document in the order of `keys`, which holds the removed document.
+    /// # Errors
+    ///
+    /// Returns `ArangoError`
+    pub async fn remove_documents<T: DeserializeOwned, Key: Serialize>(
+        &self,
+        dbc: &ArangoConnection,
+        keys: &[Key],
+    ) -> Result<BulkResponse<T>, ArangoError> {
+        let params = [("returnOld", String::from("true"))];
+        self.write_documents(dbc, Method::Delete, &params, serde_json::to_vec(keys)?).await
+    }
+
+    async fn write_documents<T: DeserializeOwned>(
+        &self,
+        dbc: &ArangoConnection,
+        method: Method,
+        params: &[(&str, String)],
+        body: Vec<u8>,
+    ) -> Result<BulkResponse<T>, ArangoError> {
+        let params: Vec<(&str, &str)> =
+            params.iter().map(|(name, value)| (*name, value.as_str())).collect();
+        let request = dbc
+            .request(method, format!("{}/{}", dbc.document(), self.name))
+            .query(&params)
+            .header("content-type", "application/json")
+            .body(body);
+        Ok(BulkResponse { results: dbc.fetch(request).await? })
+    }
+}
+
+fn return_new() -> Vec<(&'static str, String)> {
+    vec![("returnNew", String::from("true"))]
+}
+
+/// The documents of `pairs`, with the key of each pair as `_key`, as the document API expects.
+fn with_keys<Key: Serialize, Doc: Serialize>(
+    pairs: &[(Key, Doc)],
+) -> Result<Vec<Value>, serde_json::Error> {
+    pairs
+        .iter()
+        .map(|(key, doc)| {
+            let mut doc = serde_json::to_value(doc)?;
+            let Some(object) = doc.as_object_mut() else {
+                return Err(serde_json::Error::custom("a bulk update must be an object"));
+            };
+            object.insert(String::from("_key"), serde_json::to_value(key)?);
+            Ok(doc)
+        })
+        .collect()
+}
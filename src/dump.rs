@@ -0,0 +1,254 @@
+use crate::arango_api::{ArangoQuery, Collection, CursorExtractor, GetAll};
+use crate::arango_connection::ArangoConnection;
+use crate::database::Database;
+use crate::error::ArangoError;
+use crate::import::{ImportOptions, ImportSummary, ImportType};
+use futures_util::io::{AsyncBufRead, AsyncBufReadExt, AsyncWrite, AsyncWriteExt};
+use futures_util::stream::StreamExt;
+use serde::de::IgnoredAny;
+use serde_json::Value;
+use std::io::Write;
+
+/// Error of an export or of an import of a JSON lines file.
+#[derive(Debug)]
+pub enum DumpError {
+    Request(ArangoError),
+    /// The cursor API answered with an error, e.g. on a missing collection.
+    Arango(String),
+    Io(std::io::Error),
+    Json(serde_json::Error),
+    /// An unreadable or invalid line of a JSON lines file, counted from 1,
+    /// with the summary of the chunks imported before it.
+    Line {
+        line: usize,
+        imported: ImportSummary,
+        source: Box<DumpError>,
+    },
+}
+
+impl std::fmt::Display for DumpError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            DumpError::Request(err) => write!(f, "request failed: {err}"),
+            DumpError::Arango(message) => write!(f, "query failed: {message}"),
+            DumpError::Io(err) => write!(f, "i/o failed: {err}"),
+            DumpError::Json(err) => write!(f, "invalid document: {err}"),
+            DumpError::Line { line, imported, source } => {
+                write!(f, "line {line}: {source}, {} documents imported before", imported.created)
+            }
+        }
+    }
+}
+
+impl std::error::Error for DumpError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            DumpError::Request(err) => Some(err),
+            DumpError::Arango(_) => None,
+            DumpError::Io(err) => Some(err),
+            DumpError::Json(err) => Some(err),
+            DumpError::Line { source, .. } => Some(source.as_ref()),
+        }
+    }
+}
+
+impl From<ArangoError> for DumpError {
+    fn from(err: ArangoError) -> Self {
+        DumpError::Request(err)
+    }
+}
+
+impl From<std::io::Error> for DumpError {
+    fn from(err: std::io::Error) -> Self {
+        DumpError::Io(err)
+    }
+}
+
+impl From<serde_json::Error> for DumpError {
+    fn from(err: serde_json::Error) -> Self {
+        DumpError::Json(err)
+    }
+}
+
+/// Reads the documents of a batched query one batch at a time, rendered as JSON lines
+/// along with their count.
+struct ExportCursor {
+    query: Option<ArangoQuery>,
+    /// The open cursor on the server, if any.
+    cursor: Option<CursorExtractor>,
+}
+
+impl ExportCursor {
+    async fn next_batch(
+        &mut self,
+        dbc: &ArangoConnection,
+    ) -> Result<Option<(usize, Vec<u8>)>, DumpError> {
+        let response = if let Some(query) = self.query.take() {
+            query.try_exec::<Value>(dbc).await?
+        } else if let Some(cursor) = &self.cursor {
+            cursor.next::<Value>(dbc).await?
+        } else {
+            return Ok(None);
+        };
+        if response.error {
+            return Err(DumpError::Arango(response.error_message));
+        }
+        self.cursor = if response.has_more { Some(CursorExtractor(response.id)) } else { None };
+        let mut lines = vec![];
+        for document in &response.result {
+            serde_json::to_writer(&mut lines, document)?;
+            lines.push(b'\n');
+        }
+        Ok(Some((response.result.len(), lines)))
+    }
+
+    /// Deletes the cursor if the export failed before it was exhausted,
+    /// instead of leaving it open on the server until its TTL.
+    async fn close<T>(
+        &mut self,
+        dbc: &ArangoConnection,
+        exported: Result<T, DumpError>,
+    ) -> Result<T, DumpError> {
+        if let (Err(_), Some(cursor)) = (&exported, self.cursor.take()) {
+            if let Err(err) = cursor.delete(dbc).await {
+                log::debug!("Failed to delete the cursor {} of a failed export: {err}", cursor.0);
+            }
+        }
+        exported
+    }
+}
+
+impl Collection {
+    fn export_cursor(&self, filter: Option<ArangoQuery>, batch_size: usize) -> ExportCursor {
+        let query = filter.unwrap_or_else(|| self.get_all()).into_batched(batch_size);
+        ExportCursor { query: Some(query), cursor: None }
+    }
+
+    /// Writes every document of the collection into `writer` as JSON lines,
+    /// fetched through a cursor in batches of `batch_size`.
+    /// `filter` is a read query of this collection, typically built with the derived builder,
+    /// which replaces the default `get_all`. A derived read returns at most 100 documents
+    /// unless built with `unlimited`.
+    /// Returns the number of exported documents.
+    /// ```ignore
+    /// let mut file = std::fs::File::create("users.jsonl")?;
+    /// let filter = User::query_builder(collection_name).read().filter().active_eq(&true);
+    /// let filter = filter.unlimited().build();
+    /// let count = collection.export(&conn, Some(filter), &mut file, 1000).await?;
+    /// ```
+    /// # Errors
+    ///
+    /// Returns `DumpError`
+    pub async fn export<W: Write>(
+        &self,
+        dbc: &ArangoConnection,
+        filter: Option<ArangoQuery>,
+        writer: &mut W,
+        batch_size: usize,
+    ) -> Result<usize, DumpError> {
+        let mut cursor = self.export_cursor(filter, batch_size);
+        let mut count = 0;
+        let exported = async {
+            while let Some((documents, lines)) = cursor.next_batch(dbc).await? {
+                count += documents;
+                writer.write_all(&lines)?;
+            }
+            writer.flush()?;
+            Ok(count)
+        }
+        .await;
+        cursor.close(dbc, exported).await
+    }
+
+    /// Same as `export`, into an `AsyncWrite`.
+    /// # Errors
+    ///
+    /// Returns `DumpError`
+    pub async fn export_async<W: AsyncWrite + Unpin>(
+        &self,
+        dbc: &ArangoConnection,
+        filter: Option<ArangoQuery>,
+        writer: &mut W,
+        batch_size: usize,
+    ) -> Result<usize, DumpError> {
+        let mut cursor = self.export_cursor(filter, batch_size);
+        let mut count = 0;
+        let exported = async {
+            while let Some((documents, lines)) = cursor.next_batch(dbc).await? {
+                count += documents;
+                writer.write_all(&lines).await?;
+            }
+            writer.flush().await?;
+            Ok(count)
+        }
+        .await;
+        cursor.close(dbc, exported).await
+    }
+}
+
+impl Database {
+    /// Imports a JSON lines file, e.g. written by `Collection::export_async`, into `collection`
+    /// with `POST /_api/import`, in chunks of `options.chunk_size` lines. Empty lines are skipped.
+    /// The lines are sent as read, after checking that each is a JSON document.
+    ///
+    /// Every chunk is checked before it is sent. On an unreadable or invalid line the import
+    /// stops with `DumpError::Line`, the chunks before the one of the line stay imported.
+    /// ```ignore
+    /// let file = futures_util::io::BufReader::new(async_std::fs::File::open("users.jsonl").await?);
+    /// let summary = db.import_jsonl("users", file, &ImportOptions::default()).await?;
+    /// ```
+    /// # Errors
+    ///
+    /// Returns `DumpError`
+    pub async fn import_jsonl<R: AsyncBufRead + Unpin>(
+        &self,
+        collection: &str,
+        reader: R,
+        options: &ImportOptions,
+    ) -> Result<ImportSummary, DumpError> {
+        let options = options.clone().import_type(ImportType::Documents);
+        let mut lines = reader.lines().enumerate();
+        let mut summary = ImportSummary::default();
+        let mut overwrite = options.overwrite;
+        let mut chunk = vec![];
+        let mut documents = 0;
+        loop {
+            let next = lines.next().await;
+            let end = next.is_none();
+            if let Some((index, line)) = next {
+                let line = match line {
+                    Ok(line) if line.trim().is_empty() => continue,
+                    line => line.map_err(DumpError::from).and_then(|line| {
+                        serde_json::from_str::<IgnoredAny>(&line)?;
+                        Ok(line)
+                    }),
+                };
+                let line = match line {
+                    Ok(line) => line,
+                    Err(source) => {
+                        let source = Box::new(source);
+                        return Err(DumpError::Line { line: index + 1, imported: summary, source });
+                    }
+                };
+                chunk.extend_from_slice(line.as_bytes());
+                chunk.push(b'\n');
+                documents += 1;
+                if documents < options.chunk_size {
+                    continue;
+                }
+            } else if documents == 0 {
+                return Ok(summary);
+            }
+            let body = std::mem::take(&mut chunk);
+            documents = 0;
+            let imported = self.import_body(collection, body, &options, overwrite).await?;
+            let failed = imported.error;
+            summary.add(imported);
+            // only the first chunk may truncate the collection
+            overwrite = false;
+            if failed || end {
+                return Ok(summary);
+            }
+        }
+    }
+}
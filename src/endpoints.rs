@@ -0,0 +1,269 @@
+use crate::error::ArangoError;
+use crate::transport::{Method, TransportRequest, TransportResponse};
+use rand::Rng;
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Mutex, RwLock};
+use std::time::{Duration, Instant};
+
+/// How `Endpoints` picks the coordinator of a request.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Selection {
+    RoundRobin,
+    Random,
+}
+
+#[derive(Debug, Clone)]
+struct Endpoint {
+    host: String,
+    unhealthy_since: Option<Instant>,
+}
+
+/// The coordinator of an open cursor.
+#[derive(Debug, Clone)]
+struct Pin {
+    host: String,
+    used: Instant,
+}
+
+/// The coordinators of a cluster, see `ArangoConnection::with_endpoints`.
+///
+/// Requests go to the healthy coordinators in turn. A coordinator failing with a transport
+/// error is skipped for `recovery`, requests which failed to connect are sent to the next one.
+/// The continuations of a cursor always go to the coordinator which created it,
+/// until the cursor is exhausted, deleted, fails or was not used for `cursor_ttl`.
+/// ```ignore
+/// let endpoints = Endpoints::new(vec!["http://coordinator1:8529".to_owned(), "http://coordinator2:8529".to_owned()]);
+/// let conn = conn.with_endpoints(endpoints);
+/// ```
+#[derive(Debug)]
+pub struct Endpoints {
+    coordinators: RwLock<Vec<Endpoint>>,
+    selection: Selection,
+    recovery: Duration,
+    cursor_ttl: Duration,
+    next: AtomicUsize,
+    /// The coordinator of every open cursor by cursor id.
+    cursors: Mutex<HashMap<String, Pin>>,
+}
+
+/// Where a request was sent.
+pub(crate) struct Route {
+    host: String,
+    /// The cursor id of a cursor continuation.
+    cursor: Option<String>,
+    creates_cursor: bool,
+}
+
+impl Route {
+    pub(crate) fn is_pinned(&self) -> bool {
+        self.cursor.is_some()
+    }
+}
+
+#[derive(Deserialize)]
+struct Cursor {
+    #[serde(default)]
+    id: String,
+    #[serde(rename = "hasMore", default)]
+    has_more: bool,
+}
+
+#[derive(Deserialize)]
+struct ClusterEndpoint {
+    endpoint: String,
+}
+
+#[derive(Deserialize)]
+pub(crate) struct ClusterEndpoints {
+    endpoints: Vec<ClusterEndpoint>,
+}
+
+impl ClusterEndpoints {
+    /// The hosts of the endpoints, with `http` or `https` instead of `tcp` or `ssl`.
+    pub(crate) fn hosts(&self) -> Vec<String> {
+        self.endpoints.iter().map(|endpoint| http_host(&endpoint.endpoint)).collect()
+    }
+}
+
+/// The host of an endpoint, with `http` or `https` instead of `tcp` or `ssl`.
+fn http_host(endpoint: &str) -> String {
+    if let Some(address) = endpoint.strip_prefix("tcp://") {
+        format!("http://{address}")
+    } else if let Some(address) = endpoint.strip_prefix("ssl://") {
+        format!("https://{address}")
+    } else {
+        endpoint.trim_end_matches('/').to_owned()
+    }
+}
+
+/// The cursor id of the URL of a cursor continuation.
+pub(crate) fn cursor_id(url: &str) -> Option<&str> {
+    url.rsplit_once("/_api/cursor/")
+        .map(|(_, id)| id)
+        .filter(|id| !id.is_empty() && !id.contains('/'))
+}
+
+/// The leader named by a follower of an active-failover deployment,
+/// which answers with 503 and the endpoint of the leader in `x-arango-endpoint`.
+pub(crate) fn leader_of(response: &Result<TransportResponse, ArangoError>) -> Option<String> {
+    let response = response.as_ref().ok().filter(|response| response.status == 503)?;
+    response.header("x-arango-endpoint").filter(|endpoint| !endpoint.is_empty()).map(http_host)
+}
+
+impl Endpoints {
+    #[must_use]
+    pub fn new(hosts: Vec<String>) -> Self {
+        Self {
+            coordinators: RwLock::new(Self::healthy(hosts)),
+            selection: Selection::RoundRobin,
+            recovery: Duration::from_secs(30),
+            cursor_ttl: Duration::from_secs(30),
+            next: AtomicUsize::new(0),
+            cursors: Mutex::new(HashMap::new()),
+        }
+    }
+
+    #[must_use]
+    pub fn selection(self, selection: Selection) -> Self {
+        Self { selection, ..self }
+    }
+
+    #[must_use]
+    /// How long a failed coordinator is skipped, 30 seconds by default.
+    pub fn recovery(self, recovery: Duration) -> Self {
+        Self { recovery, ..self }
+    }
+
+    #[must_use]
+    /// How long an unused cursor stays pinned to its coordinator, 30 seconds by default
+    /// like the cursor TTL of the server. Set it to the `ttl` of the queries if they set one.
+    pub fn cursor_ttl(self, cursor_ttl: Duration) -> Self {
+        Self { cursor_ttl, ..self }
+    }
+
+    fn healthy(hosts: Vec<String>) -> Vec<Endpoint> {
+        hosts.into_iter().map(|host| Endpoint { host, unhealthy_since: None }).collect()
+    }
+
+    /// The hosts of the coordinators.
+    /// # Panics
+    ///
+    /// Panics if a thread panicked while updating the endpoints.
+    #[must_use]
+    pub fn hosts(&self) -> Vec<String> {
+        self.coordinators.read().unwrap().iter().map(|endpoint| endpoint.host.clone()).collect()
+    }
+
+    /// Replaces the coordinators, all of them healthy.
+    /// Open cursors stay pinned to their coordinators if these are still in `hosts`.
+    /// # Panics
+    ///
+    /// Panics if a thread panicked while updating the endpoints.
+    pub fn set_hosts(&self, hosts: Vec<String>) {
+        self.cursors.lock().unwrap().retain(|_, pin| hosts.contains(&pin.host));
+        *self.coordinators.write().unwrap() = Self::healthy(hosts);
+    }
+
+    pub(crate) fn len(&self) -> usize {
+        self.coordinators.read().unwrap().len()
+    }
+
+    /// A healthy coordinator, or any if none is healthy.
+    fn select(&self) -> Option<String> {
+        let endpoints = self.coordinators.read().unwrap();
+        let count = endpoints.len();
+        if count == 0 {
+            return None;
+        }
+        let start = match self.selection {
+            Selection::RoundRobin => self.next.fetch_add(1, Ordering::Relaxed),
+            Selection::Random => rand::thread_rng().gen_range(0..count),
+        };
+        let healthy =
+            (0..count).map(|offset| &endpoints[(start + offset) % count]).find(|endpoint| {
+                !matches!(endpoint.unhealthy_since, Some(since) if since.elapsed() < self.recovery)
+            });
+        Some(healthy.unwrap_or(&endpoints[start % count]).host.clone())
+    }
+
+    fn set_health(&self, host: &str, healthy: bool) {
+        let mut endpoints = self.coordinators.write().unwrap();
+        if let Some(endpoint) = endpoints.iter_mut().find(|endpoint| endpoint.host == host) {
+            endpoint.unhealthy_since = if healthy { None } else { Some(Instant::now()) };
+        }
+    }
+
+    /// Sends `request`, built for `base`, to the coordinator of its cursor,
+    /// else to `leader` if known or to a selected one.
+    pub(crate) fn route(
+        &self,
+        base: &str,
+        leader: Option<&str>,
+        request: &mut TransportRequest,
+    ) -> Option<Route> {
+        let path = request.url.strip_prefix(base)?.to_owned();
+        let cursor = cursor_id(&path).map(str::to_owned);
+        let pinned = cursor.as_ref().and_then(|id| self.pinned(id));
+        let host = match (pinned, leader) {
+            (Some(host), _) => host,
+            (None, Some(leader)) => leader.to_owned(),
+            (None, None) => self.select()?,
+        };
+        request.url = format!("{host}{path}");
+        let creates_cursor = request.method == Method::Post && path.ends_with("/_api/cursor");
+        Some(Route { host, cursor, creates_cursor })
+    }
+
+    /// The coordinator of the cursor `id`, unless the cursor expired.
+    fn pinned(&self, id: &str) -> Option<String> {
+        let mut cursors = self.cursors.lock().unwrap();
+        let pin = cursors.get_mut(id)?;
+        if pin.used.elapsed() >= self.cursor_ttl {
+            cursors.remove(id);
+            return None;
+        }
+        pin.used = Instant::now();
+        Some(pin.host.clone())
+    }
+
+    /// Tracks the health of the coordinator and the cursors created on it.
+    /// A cursor is unpinned on any failure, it is gone or unusable then.
+    pub(crate) fn observe(
+        &self,
+        route: &Route,
+        method: Method,
+        response: &Result<TransportResponse, ArangoError>,
+    ) {
+        let response = match response {
+            Ok(response) => response,
+            Err(err) => {
+                if let ArangoError::Connect(_) | ArangoError::Transport(_) = err {
+                    log::debug!("Coordinator {} is unhealthy", route.host);
+                    self.set_health(&route.host, false);
+                }
+                if let Some(id) = &route.cursor {
+                    self.cursors.lock().unwrap().remove(id);
+                }
+                return;
+            }
+        };
+        self.set_health(&route.host, true);
+        let cursor = serde_json::from_slice::<Cursor>(&response.body)
+            .ok()
+            .filter(|cursor| response.status < 400 && cursor.has_more);
+        let mut cursors = self.cursors.lock().unwrap();
+        if let Some(id) = &route.cursor {
+            if method == Method::Delete || cursor.is_none() {
+                cursors.remove(id);
+            }
+        } else if let Some(cursor) = cursor.filter(|_| route.creates_cursor) {
+            // the pins of cursors dropped without exhausting or deleting them expire here
+            let ttl = self.cursor_ttl;
+            cursors.retain(|_, pin| pin.used.elapsed() < ttl);
+            let pin = Pin { host: route.host.clone(), used: Instant::now() };
+            cursors.insert(cursor.id, pin);
+        }
+    }
+}
@@ -0,0 +1,67 @@
+use crate::queries::ArangoStatus;
+
+/// Error of a request to the database.
+///
+/// Errors reported by the database itself, like a conflict or a syntax error in a query,
+/// are not `ArangoError`s but responses with `error: true`, except for the requests whose
+/// result has no such field, which fail with `ArangoError::Server`.
+#[derive(Debug)]
+pub enum ArangoError {
+    /// The transport could not connect to the server, the request was not sent.
+    Connect(Box<dyn std::error::Error + Send + Sync>),
+    /// The transport could not send the request or read the response.
+    Transport(Box<dyn std::error::Error + Send + Sync>),
+    /// No response arrived within the timeout of the request.
+    Timeout(Box<dyn std::error::Error + Send + Sync>),
+    /// The response body is not the expected JSON.
+    Json(serde_json::Error),
+    /// The server refused a request whose result has no `error` field, e.g. with 403.
+    Server(ArangoStatus),
+}
+
+impl std::fmt::Display for ArangoError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            ArangoError::Connect(err) => write!(f, "connection failed: {err}"),
+            ArangoError::Transport(err) => write!(f, "transport failed: {err}"),
+            ArangoError::Timeout(err) => write!(f, "request timed out: {err}"),
+            ArangoError::Json(err) => write!(f, "invalid response: {err}"),
+            ArangoError::Server(status) => write!(
+                f,
+                "server error {} ({}): {}",
+                status.code, status.error_num, status.error_message
+            ),
+        }
+    }
+}
+
+impl std::error::Error for ArangoError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            ArangoError::Connect(err) | ArangoError::Transport(err) | ArangoError::Timeout(err) => {
+                Some(err.as_ref())
+            }
+            ArangoError::Json(err) => Some(err),
+            ArangoError::Server(_) => None,
+        }
+    }
+}
+
+impl From<serde_json::Error> for ArangoError {
+    fn from(err: serde_json::Error) -> Self {
+        ArangoError::Json(err)
+    }
+}
+
+#[cfg(feature = "reqwest")]
+impl From<reqwest::Error> for ArangoError {
+    fn from(err: reqwest::Error) -> Self {
+        if err.is_connect() {
+            ArangoError::Connect(Box::new(err))
+        } else if err.is_timeout() {
+            ArangoError::Timeout(Box::new(err))
+        } else {
+            ArangoError::Transport(Box::new(err))
+        }
+    }
+}
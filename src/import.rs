@@ -0,0 +1,232 @@
+use crate::database::Database;
+use crate::error::ArangoError;
+use crate::transport::Method;
+use futures_util::stream::{self, Stream, StreamExt};
+use serde::{Deserialize, Serialize};
+
+/// Document format of the request bodies of `POST /_api/import`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ImportType {
+    /// One JSON array of documents per request, `type=array`.
+    Array,
+    /// One JSON document per line, `type=documents`.
+    Documents,
+}
+
+/// What happens to imported documents with a `_key` which already exists.
+#[derive(Debug, Serialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum OnDuplicate {
+    /// Counts the document as error, the default.
+    Error,
+    /// Updates the existing document.
+    Update,
+    /// Replaces the existing document.
+    Replace,
+    /// Keeps the existing document.
+    Ignore,
+}
+
+/// Options of `Database::import`.
+/// ```ignore
+/// let options = ImportOptions::default().on_duplicate(OnDuplicate::Replace).chunk_size(5_000);
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct ImportOptions {
+    pub(crate) import_type: ImportType,
+    pub(crate) on_duplicate: OnDuplicate,
+    pub(crate) complete: bool,
+    pub(crate) details: bool,
+    pub(crate) overwrite: bool,
+    pub(crate) chunk_size: usize,
+}
+
+impl Default for ImportOptions {
+    fn default() -> Self {
+        Self {
+            import_type: ImportType::Array,
+            on_duplicate: OnDuplicate::Error,
+            complete: false,
+            details: false,
+            overwrite: false,
+            chunk_size: 10_000,
+        }
+    }
+}
+
+impl ImportOptions {
+    #[must_use]
+    pub fn import_type(self, import_type: ImportType) -> Self {
+        Self { import_type, ..self }
+    }
+
+    #[must_use]
+    pub fn on_duplicate(self, on_duplicate: OnDuplicate) -> Self {
+        Self { on_duplicate, ..self }
+    }
+
+    #[must_use]
+    /// Aborts the import of a chunk on the first error, nothing of the chunk is imported then.
+    pub fn complete(self, complete: bool) -> Self {
+        Self { complete, ..self }
+    }
+
+    #[must_use]
+    /// Returns a message per failed document in `ImportSummary::details`.
+    pub fn details(self, details: bool) -> Self {
+        Self { details, ..self }
+    }
+
+    #[must_use]
+    /// Removes all documents of the collection before the import of the first chunk.
+    pub fn overwrite(self, overwrite: bool) -> Self {
+        Self { overwrite, ..self }
+    }
+
+    #[must_use]
+    /// The number of documents sent per request.
+    pub fn chunk_size(self, chunk_size: usize) -> Self {
+        Self { chunk_size: chunk_size.max(1), ..self }
+    }
+}
+
+/// Result of an import, summed up over all chunks.
+#[derive(Debug, Default, Serialize, Deserialize, PartialEq, Clone)]
+pub struct ImportSummary {
+    #[serde(default)]
+    pub error: bool,
+    #[serde(rename = "errorMessage", skip_serializing_if = "String::is_empty", default)]
+    pub error_message: String,
+    #[serde(default)]
+    pub created: usize,
+    #[serde(default)]
+    pub errors: usize,
+    #[serde(default)]
+    pub empty: usize,
+    #[serde(default)]
+    pub updated: usize,
+    #[serde(default)]
+    pub ignored: usize,
+    #[serde(skip_serializing_if = "Vec::is_empty", default)]
+    pub details: Vec<String>,
+}
+
+impl ImportSummary {
+    pub(crate) fn add(&mut self, chunk: Self) {
+        self.error |= chunk.error;
+        if !chunk.error_message.is_empty() {
+            self.error_message = chunk.error_message;
+        }
+        self.created += chunk.created;
+        self.errors += chunk.errors;
+        self.empty += chunk.empty;
+        self.updated += chunk.updated;
+        self.ignored += chunk.ignored;
+        self.details.extend(chunk.details);
+    }
+}
+
+impl Database {
+    /// Imports `items` into `collection` with `POST /_api/import`, in chunks of
+    /// `options.chunk_size` documents. Much faster than AQL inserts for large loads.
+    /// Stops at the first chunk which fails as a whole, e.g. on a missing collection.
+    /// ```ignore
+    /// let summary = db.import("People", people, &ImportOptions::default()).await?;
+    /// ```
+    /// # Errors
+    ///
+    /// Returns `ArangoError`, `ArangoError::Json` if an item fails to serialize,
+    /// the chunks before the one of the item stay imported.
+    pub async fn import<T, I>(
+        &self,
+        collection: &str,
+        items: I,
+        options: &ImportOptions,
+    ) -> Result<ImportSummary, ArangoError>
+    where
+        T: Serialize,
+        I: IntoIterator<Item = T>,
+    {
+        self.import_stream(collection, stream::iter(items), options).await
+    }
+
+    /// Same as `import`, with the documents read from a `Stream`.
+    /// # Errors
+    ///
+    /// Returns `ArangoError`
+    pub async fn import_stream<T, S>(
+        &self,
+        collection: &str,
+        items: S,
+        options: &ImportOptions,
+    ) -> Result<ImportSummary, ArangoError>
+    where
+        T: Serialize,
+        S: Stream<Item = T>,
+    {
+        let mut summary = ImportSummary::default();
+        let mut chunks = Box::pin(items.chunks(options.chunk_size));
+        let mut overwrite = options.overwrite;
+        while let Some(chunk) = chunks.next().await {
+            let imported = self.import_chunk(collection, &chunk, options, overwrite).await?;
+            let failed = imported.error;
+            summary.add(imported);
+            if failed {
+                break;
+            }
+            // only the first chunk may truncate the collection
+            overwrite = false;
+        }
+        Ok(summary)
+    }
+
+    async fn import_chunk<T: Serialize>(
+        &self,
+        collection: &str,
+        chunk: &[T],
+        options: &ImportOptions,
+        overwrite: bool,
+    ) -> Result<ImportSummary, ArangoError> {
+        let body = match options.import_type {
+            ImportType::Array => serde_json::to_vec(chunk)?,
+            ImportType::Documents => {
+                let mut body = vec![];
+                for item in chunk {
+                    serde_json::to_writer(&mut body, item)?;
+                    body.push(b'\n');
+                }
+                body
+            }
+        };
+        self.import_body(collection, body, options, overwrite).await
+    }
+
+    /// Imports a chunk already serialized as `options.import_type`.
+    pub(crate) async fn import_body(
+        &self,
+        collection: &str,
+        body: Vec<u8>,
+        options: &ImportOptions,
+        overwrite: bool,
+    ) -> Result<ImportSummary, ArangoError> {
+        let (import_type, content_type) = match options.import_type {
+            ImportType::Array => ("array", "application/json"),
+            ImportType::Documents => ("documents", "application/x-ldjson"),
+        };
+        let on_duplicate = serde_json::to_value(options.on_duplicate).unwrap();
+        let request = self
+            .connection
+            .request(Method::Post, self.connection.import())
+            .query(&[
+                ("collection", collection),
+                ("type", import_type),
+                ("onDuplicate", on_duplicate.as_str().unwrap_or_default()),
+                ("complete", &options.complete.to_string()),
+                ("details", &options.details.to_string()),
+                ("overwrite", &overwrite.to_string()),
+            ])
+            .header("content-type", content_type)
+            .body(body);
+        self.connection.send(request, None).await
+    }
+}
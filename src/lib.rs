@@ -5,11 +5,25 @@
 #[cfg(feature = "actors")]
 pub mod actor;
 
+#[cfg(feature = "blocking")]
+pub mod blocking;
+
 pub mod arango_api;
 pub mod arango_connection;
 pub mod arango_response;
+pub mod bulk;
 pub mod database;
+pub mod dump;
+pub mod endpoints;
+pub mod error;
+pub mod import;
+pub mod metrics;
+pub mod middleware;
+pub mod queries;
+pub mod result_cache;
+pub mod retry;
 pub mod test;
+pub mod transport;
 
 #[cfg(feature = "actors")]
 pub use actor::*;
@@ -18,6 +32,16 @@ pub use arango_api::*;
 pub use arango_connection::*;
 pub use arango_response::*;
 pub use database::*;
+pub use dump::*;
+pub use endpoints::*;
+pub use error::*;
+pub use import::*;
+pub use metrics::*;
+pub use middleware::*;
+pub use queries::*;
+pub use result_cache::*;
+pub use retry::*;
+pub use transport::*;
 
 mod arango_impl;
 mod arango_test;
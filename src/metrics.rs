@@ -0,0 +1,96 @@
+use crate::arango_api::ArangoQuery;
+use crate::middleware::{Exchange, Middleware};
+use crate::transport::TransportRequest;
+use serde::Deserialize;
+use std::sync::Arc;
+
+/// Receives the metrics of an `ArangoConnection`, e.g. to export them to Prometheus.
+/// `labels` are pairs of label name and value.
+pub trait MetricsRecorder: Send + Sync {
+    fn observe(&self, name: &'static str, labels: &[(&'static str, &str)], value: f64);
+    fn increment(&self, name: &'static str, labels: &[(&'static str, &str)], value: u64);
+}
+
+/// Histogram of the client side latency in seconds, by `query`.
+pub const REQUEST_DURATION: &str = "arangodb_request_duration_seconds";
+/// Histogram of `ArangoStats::execution_time` in seconds, by `query`.
+pub const EXECUTION_TIME: &str = "arangodb_query_execution_time_seconds";
+/// Counter of scanned documents, by `query` and `scan`, `full` or `index`.
+pub const DOCUMENTS_SCANNED: &str = "arangodb_documents_scanned_total";
+/// Counter of write operations, by `query` and `result`, `executed` or `ignored`.
+pub const WRITES: &str = "arangodb_writes_total";
+/// Counter of failed requests, by `query` and `code`, the `errorNum` of the response,
+/// the HTTP status without one or `transport`.
+pub const ERRORS: &str = "arangodb_errors_total";
+/// Counter of requests answered by the `ResultCache`, without a request to the server, by `query`.
+pub const CACHE_HITS: &str = "arangodb_result_cache_hits_total";
+
+/// The `Middleware` recording the metrics of every request, see `ArangoConnection::with_metrics`.
+///
+/// The `query` label is the label of the query set with `ArangoQuery::with_label`,
+/// `query` for unlabeled queries, or the API of other requests, like `cursor` or `import`.
+pub struct Metrics {
+    recorder: Arc<dyn MetricsRecorder>,
+}
+
+impl Metrics {
+    #[must_use]
+    pub fn new(recorder: Arc<dyn MetricsRecorder>) -> Self {
+        Self { recorder }
+    }
+}
+
+#[derive(Deserialize)]
+struct ErrorNum {
+    #[serde(rename = "errorNum")]
+    error_num: u64,
+}
+
+/// The API of `url`, the path segment after `_api`.
+fn api_name(url: &str) -> &str {
+    url.split("/_api/").nth(1).and_then(|api| api.split('/').next()).unwrap_or("unknown")
+}
+
+/// The `query` label of `request`.
+fn label<'a>(request: &'a TransportRequest, query: Option<&'a ArangoQuery>) -> &'a str {
+    match query {
+        Some(query) => query.label().unwrap_or("query"),
+        None => api_name(&request.url),
+    }
+}
+
+impl Middleware for Metrics {
+    fn after_receive(&self, exchange: &Exchange) {
+        let query = ("query", label(exchange.request, exchange.query));
+        self.recorder.observe(REQUEST_DURATION, &[query], exchange.elapsed.as_secs_f64());
+        if let Some(stats) = exchange.stats() {
+            self.recorder.observe(EXECUTION_TIME, &[query], stats.execution_time);
+            let counters = [
+                (DOCUMENTS_SCANNED, ("scan", "full"), stats.scanned_full),
+                (DOCUMENTS_SCANNED, ("scan", "index"), stats.scanned_index),
+                (WRITES, ("result", "executed"), stats.writes_executed),
+                (WRITES, ("result", "ignored"), stats.writes_ignored),
+            ];
+            for (name, kind, count) in &counters {
+                self.recorder.increment(name, &[query, *kind], *count as u64);
+            }
+        }
+        let code = match exchange.response {
+            Ok(response) if response.status >= 400 => {
+                Some(serde_json::from_slice::<ErrorNum>(&response.body).map_or_else(
+                    |_| response.status.to_string(),
+                    |body| body.error_num.to_string(),
+                ))
+            }
+            Ok(_) => None,
+            Err(_) => Some("transport".to_owned()),
+        };
+        if let Some(code) = code {
+            self.recorder.increment(ERRORS, &[query, ("code", &code)], 1);
+        }
+    }
+
+    fn cache_hit(&self, request: &TransportRequest, query: Option<&ArangoQuery>) {
+        self.recorder.increment(CACHE_HITS, &[("query", label(request, query))], 1);
+    }
+}
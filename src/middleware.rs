@@ -0,0 +1,119 @@
+use crate::arango_api::ArangoQuery;
+use crate::arango_response::{ArangoStats, ResponseExtra};
+use crate::error::ArangoError;
+use crate::transport::{TransportRequest, TransportResponse};
+use serde::de::IgnoredAny;
+use serde::Deserialize;
+use std::time::Duration;
+
+/// Hooks around every request of an `ArangoConnection`, added with `with_middleware`.
+/// Hooks run in the order the middleware was added.
+/// ```ignore
+/// struct CorrelationId(String);
+///
+/// impl Middleware for CorrelationId {
+///     fn before_send(&self, request: &mut TransportRequest, _query: Option<&ArangoQuery>) {
+///         request.headers.push(("x-correlation-id".to_owned(), self.0.clone()));
+///     }
+/// }
+///
+/// let conn = conn.with_middleware(CorrelationId(request_id));
+/// ```
+pub trait Middleware: Send + Sync {
+    /// Called before `request` is sent, e.g. to add headers.
+    /// `query` is the executed query for the requests of `ArangoQuery::try_exec`.
+    fn before_send(&self, _request: &mut TransportRequest, _query: Option<&ArangoQuery>) {}
+
+    /// Called when the response arrived or the transport failed.
+    fn after_receive(&self, _exchange: &Exchange) {}
+
+    /// Called instead of `after_receive` when the `ResultCache` of the connection answers
+    /// `request`, which is not sent then.
+    fn cache_hit(&self, _request: &TransportRequest, _query: Option<&ArangoQuery>) {}
+}
+
+/// A finished request, as seen by `Middleware::after_receive`.
+pub struct Exchange<'a> {
+    /// The request as sent, without its body.
+    pub request: &'a TransportRequest,
+    pub query: Option<&'a ArangoQuery>,
+    pub response: Result<&'a TransportResponse, &'a ArangoError>,
+    /// Time from sending the request until the response was read.
+    pub elapsed: Duration,
+}
+
+#[derive(Deserialize)]
+struct Extra {
+    extra: Option<ResponseExtra>,
+}
+
+#[derive(Deserialize)]
+struct Results {
+    result: Vec<IgnoredAny>,
+}
+
+impl Exchange<'_> {
+    #[must_use]
+    pub fn status(&self) -> Option<u16> {
+        self.response.ok().map(|response| response.status)
+    }
+
+    /// The query statistics of a cursor response.
+    #[must_use]
+    pub fn stats(&self) -> Option<ArangoStats> {
+        let response = self.response.ok()?;
+        Some(serde_json::from_slice::<Extra>(&response.body).ok()?.extra?.stats)
+    }
+
+    /// The number of documents in the `result` of a cursor response.
+    #[must_use]
+    pub fn result_count(&self) -> Option<usize> {
+        let response = self.response.ok()?;
+        Some(serde_json::from_slice::<Results>(&response.body).ok()?.result.len())
+    }
+}
+
+/// The span of a request with the `tracing` feature.
+/// Bind variables are recorded by name only, their values may be sensitive.
+#[cfg(feature = "tracing")]
+pub(crate) fn request_span(
+    request: &TransportRequest,
+    query: Option<&ArangoQuery>,
+) -> tracing::Span {
+    use tracing::field::Empty;
+
+    let span = tracing::debug_span!(
+        "arangodb.request",
+        method = ?request.method,
+        url = %request.url,
+        query = Empty,
+        bind_vars = Empty,
+        status = Empty,
+        elapsed_ms = Empty,
+        execution_time = Empty,
+        result_count = Empty,
+    );
+    if let Some(query) = query {
+        let names: Vec<&str> = query.bind_vars.keys().map(String::as_str).collect();
+        span.record("query", query.query.as_str());
+        span.record("bind_vars", names.join(",").as_str());
+    }
+    span
+}
+
+#[cfg(feature = "tracing")]
+pub(crate) fn record(span: &tracing::Span, exchange: &Exchange) {
+    if let Some(status) = exchange.status() {
+        span.record("status", status);
+    }
+    span.record("elapsed_ms", exchange.elapsed.as_secs_f64() * 1000.0);
+    if let Some(stats) = exchange.stats() {
+        span.record("execution_time", stats.execution_time);
+    }
+    if let Some(count) = exchange.result_count() {
+        span.record("result_count", count);
+    }
+    if let Err(err) = exchange.response {
+        tracing::debug!(parent: span, error = %err, "request failed");
+    }
+}
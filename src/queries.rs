@@ -0,0 +1,247 @@
+use crate::database::Database;
+use crate::error::ArangoError;
+use crate::transport::Method;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::BTreeMap;
+
+/// A running or slow query of `Database::current_queries` and `Database::slow_queries`.
+#[derive(Debug, PartialEq, Serialize, Deserialize, Clone, Default)]
+pub struct RunningQuery {
+    /// The id to kill the query with `Database::kill_query`.
+    pub id: String,
+    #[serde(default)]
+    pub database: String,
+    #[serde(default)]
+    pub user: String,
+    pub query: String,
+    /// Only tracked with `QueryTracking::track_bind_vars`.
+    #[serde(rename = "bindVars", default)]
+    pub bind_vars: BTreeMap<String, Value>,
+    /// The start time, as ISO 8601 timestamp.
+    #[serde(default)]
+    pub started: String,
+    /// The run time in seconds, so far for running queries.
+    #[serde(rename = "runTime", default)]
+    pub run_time: f64,
+    #[serde(rename = "peakMemoryUsage", default)]
+    pub peak_memory_usage: usize,
+    /// `executing` for running queries, `finished` or `killed` for slow ones.
+    #[serde(default)]
+    pub state: String,
+    /// Whether the query is a streaming cursor.
+    #[serde(default)]
+    pub stream: bool,
+}
+
+/// The query tracking of the database, read with `Database::query_tracking`.
+/// ```ignore
+/// let tracking = db.query_tracking().await?;
+/// db.set_query_tracking(&QueryTracking { slow_query_threshold: 2.0, ..tracking }).await?;
+/// ```
+#[derive(Debug, PartialEq, Serialize, Deserialize, Clone)]
+pub struct QueryTracking {
+    /// Whether running queries are tracked at all.
+    pub enabled: bool,
+    #[serde(rename = "trackSlowQueries")]
+    pub track_slow_queries: bool,
+    #[serde(rename = "trackBindVars")]
+    pub track_bind_vars: bool,
+    /// The number of slow queries kept.
+    #[serde(rename = "maxSlowQueries")]
+    pub max_slow_queries: usize,
+    /// Seconds after which a query counts as slow.
+    #[serde(rename = "slowQueryThreshold")]
+    pub slow_query_threshold: f64,
+    /// Seconds after which a streaming query counts as slow.
+    #[serde(rename = "slowStreamingQueryThreshold")]
+    pub slow_streaming_query_threshold: f64,
+    /// The length query strings are truncated to.
+    #[serde(rename = "maxQueryStringLength")]
+    pub max_query_string_length: usize,
+}
+
+/// When the results of queries are cached by the server.
+#[derive(Debug, Serialize, Deserialize, PartialEq, Clone, Copy)]
+#[serde(rename_all = "lowercase")]
+pub enum QueryCacheMode {
+    Off,
+    /// Caches all queries but the ones with `ArangoQuery::cache(false)`.
+    On,
+    /// Caches only queries with `ArangoQuery::cache(true)`.
+    Demand,
+}
+
+/// The query results cache of the database, read with `Database::query_cache_properties`.
+/// ```ignore
+/// let properties = db.query_cache_properties().await?;
+/// db.set_query_cache_properties(&QueryCacheProperties { mode: QueryCacheMode::Demand, ..properties })
+///     .await?;
+/// ```
+#[derive(Debug, PartialEq, Serialize, Deserialize, Clone)]
+pub struct QueryCacheProperties {
+    pub mode: QueryCacheMode,
+    /// The number of results cached per database.
+    #[serde(rename = "maxResults")]
+    pub max_results: usize,
+    /// The size in bytes of the results cached per database.
+    #[serde(rename = "maxResultsSize")]
+    pub max_results_size: usize,
+    /// The size in bytes of the largest cached result.
+    #[serde(rename = "maxEntrySize")]
+    pub max_entry_size: usize,
+    /// Whether queries of system collections are cached.
+    #[serde(rename = "includeSystem")]
+    pub include_system: bool,
+}
+
+/// A cached query result of `Database::query_cache_entries`.
+#[derive(Debug, PartialEq, Serialize, Deserialize, Clone, Default)]
+pub struct QueryCacheEntry {
+    #[serde(default)]
+    pub hash: String,
+    pub query: String,
+    #[serde(rename = "bindVars", default)]
+    pub bind_vars: BTreeMap<String, Value>,
+    /// The size of the result in bytes.
+    #[serde(default)]
+    pub size: usize,
+    /// The number of documents in the result.
+    #[serde(default)]
+    pub results: usize,
+    /// The number of times the result was read from the cache.
+    #[serde(default)]
+    pub hits: usize,
+    /// The run time of the query in seconds.
+    #[serde(rename = "runTime", default)]
+    pub run_time: f64,
+    /// The time the result was cached, as ISO 8601 timestamp.
+    #[serde(default)]
+    pub started: String,
+    /// The collections and views of the query, a write to one of them drops the entry.
+    #[serde(rename = "dataSources", default)]
+    pub data_sources: Vec<String>,
+}
+
+/// The response of a request without a result, like `Database::kill_query`.
+#[derive(Debug, PartialEq, Serialize, Deserialize, Clone, Default)]
+pub struct ArangoStatus {
+    #[serde(default)]
+    pub error: bool,
+    #[serde(default)]
+    pub code: u16,
+    #[serde(rename = "errorMessage", skip_serializing_if = "String::is_empty", default)]
+    pub error_message: String,
+    #[serde(rename = "errorNum", skip_serializing, default)]
+    pub error_num: u64,
+}
+
+impl Database {
+    /// The queries running in this database, with `GET /_api/query/current`.
+    /// # Errors
+    ///
+    /// Returns `ArangoError`, `ArangoError::Server` if the server refuses the request.
+    pub async fn current_queries(&self) -> Result<Vec<RunningQuery>, ArangoError> {
+        let url = format!("{}/current", self.connection.queries());
+        self.connection.fetch(self.connection.request(Method::Get, url)).await
+    }
+
+    /// The slow queries of this database, with `GET /_api/query/slow`.
+    /// # Errors
+    ///
+    /// Returns `ArangoError`, `ArangoError::Server` if the server refuses the request.
+    pub async fn slow_queries(&self) -> Result<Vec<RunningQuery>, ArangoError> {
+        let url = format!("{}/slow", self.connection.queries());
+        self.connection.fetch(self.connection.request(Method::Get, url)).await
+    }
+
+    /// Clears the list of slow queries, with `DELETE /_api/query/slow`.
+    /// # Errors
+    ///
+    /// Returns `ArangoError`
+    pub async fn clear_slow_queries(&self) -> Result<ArangoStatus, ArangoError> {
+        let url = format!("{}/slow", self.connection.queries());
+        self.connection.send(self.connection.request(Method::Delete, url), None).await
+    }
+
+    /// Kills the running query `id`, with `DELETE /_api/query/{id}`.
+    /// A query which already finished fails with `errorNum` 1591.
+    /// ```ignore
+    /// for query in db.current_queries().await? {
+    ///     if query.run_time > 60.0 {
+    ///         db.kill_query(&query.id).await?;
+    ///     }
+    /// }
+    /// ```
+    /// # Errors
+    ///
+    /// Returns `ArangoError`
+    pub async fn kill_query(&self, id: &str) -> Result<ArangoStatus, ArangoError> {
+        let url = format!("{}/{id}", self.connection.queries());
+        self.connection.send(self.connection.request(Method::Delete, url), None).await
+    }
+
+    /// The query tracking settings, with `GET /_api/query/properties`.
+    /// # Errors
+    ///
+    /// Returns `ArangoError`, `ArangoError::Server` if the server refuses the request.
+    pub async fn query_tracking(&self) -> Result<QueryTracking, ArangoError> {
+        let url = format!("{}/properties", self.connection.queries());
+        self.connection.fetch(self.connection.request(Method::Get, url)).await
+    }
+
+    /// Changes the query tracking settings, with `PUT /_api/query/properties`.
+    /// Returns the settings now in effect.
+    /// # Errors
+    ///
+    /// Returns `ArangoError`, `ArangoError::Server` if the server refuses the request.
+    pub async fn set_query_tracking(
+        &self,
+        tracking: &QueryTracking,
+    ) -> Result<QueryTracking, ArangoError> {
+        let url = format!("{}/properties", self.connection.queries());
+        let request = self.connection.request(Method::Put, url).json(tracking);
+        self.connection.fetch(request).await
+    }
+
+    /// The query results cache settings, with `GET /_api/query-cache/properties`.
+    /// # Errors
+    ///
+    /// Returns `ArangoError`, `ArangoError::Server` if the server refuses the request.
+    pub async fn query_cache_properties(&self) -> Result<QueryCacheProperties, ArangoError> {
+        let url = format!("{}/properties", self.connection.query_cache());
+        self.connection.fetch(self.connection.request(Method::Get, url)).await
+    }
+
+    /// Changes the query results cache settings, with `PUT /_api/query-cache/properties`.
+    /// Returns the settings now in effect.
+    /// # Errors
+    ///
+    /// Returns `ArangoError`, `ArangoError::Server` if the server refuses the request.
+    pub async fn set_query_cache_properties(
+        &self,
+        properties: &QueryCacheProperties,
+    ) -> Result<QueryCacheProperties, ArangoError> {
+        let url = format!("{}/properties", self.connection.query_cache());
+        let request = self.connection.request(Method::Put, url).json(properties);
+        self.connection.fetch(request).await
+    }
+
+    /// The cached query results, with `GET /_api/query-cache/entries`.
+    /// # Errors
+    ///
+    /// Returns `ArangoError`, `ArangoError::Server` if the server refuses the request.
+    pub async fn query_cache_entries(&self) -> Result<Vec<QueryCacheEntry>, ArangoError> {
+        let url = format!("{}/entries", self.connection.query_cache());
+        self.connection.fetch(self.connection.request(Method::Get, url)).await
+    }
+
+    /// Drops all cached query results, with `DELETE /_api/query-cache`.
+    /// # Errors
+    ///
+    /// Returns `ArangoError`
+    pub async fn clear_query_cache(&self) -> Result<ArangoStatus, ArangoError> {
+        let request = self.connection.request(Method::Delete, self.connection.query_cache());
+        self.connection.send(request, None).await
+    }
+}
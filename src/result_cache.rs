@@ -0,0 +1,250 @@
+use crate::arango_api::ArangoQuery;
+use crate::retry::is_read_only;
+use crate::transport::{Method, TransportRequest, TransportResponse};
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+/// An in-process cache of query results, see `ArangoConnection::with_result_cache`.
+///
+/// Read-only queries are answered from the cache while their result is younger than `ttl`,
+/// without sending a request, so `Middleware::after_receive` only sees misses
+/// and `Middleware::cache_hit` the hits.
+/// Results are cached by the query as sent, only complete ones without further batches.
+/// Only the reads binding their collections with `@@` parameters are cached,
+/// the collections a query names in its text are not known to the cache.
+/// A write query invalidates the results of the collections it binds with `@@` parameters,
+/// like the queries of `Collection` do, or all results if it binds none.
+/// Imports and the writes of the document API invalidate their collection.
+/// Writes by other clients are only seen after `ttl`.
+/// ```ignore
+/// let conn = conn.with_result_cache(ResultCache::default().ttl(Duration::from_secs(5)));
+/// ```
+#[derive(Debug)]
+pub struct ResultCache {
+    ttl: Duration,
+    max_entries: usize,
+    entries: Mutex<HashMap<String, Entry>>,
+    generations: Mutex<Generations>,
+    hits: AtomicU64,
+    misses: AtomicU64,
+}
+
+#[derive(Debug)]
+struct Entry {
+    response: TransportResponse,
+    collections: Vec<String>,
+    stored: Instant,
+}
+
+/// Counts the invalidations, so that a result read before a write is not cached after it.
+#[derive(Debug, Default)]
+struct Generations {
+    /// Incremented by `ResultCache::clear`.
+    all: u64,
+    collections: HashMap<String, u64>,
+}
+
+impl Generations {
+    /// Changes with every invalidation of `collections`.
+    fn of(&self, collections: &[String]) -> u64 {
+        let generations = collections.iter().filter_map(|name| self.collections.get(name));
+        generations.fold(self.all, |sum, generation| sum.wrapping_add(*generation))
+    }
+}
+
+/// The hits and misses of a `ResultCache` since it was created.
+#[derive(Debug, Default, PartialEq, Clone, Copy)]
+pub struct CacheStats {
+    pub hits: u64,
+    pub misses: u64,
+    /// The number of cached results, including expired ones not yet evicted.
+    pub entries: usize,
+}
+
+/// A request the cache takes part in, see `ResultCache::admit`.
+pub(crate) enum Admission {
+    /// A read-only query, answered from the cache.
+    Read(CacheKey),
+    /// A write to the collections, to all of them if `None`.
+    Write(Option<Vec<String>>),
+}
+
+/// A cacheable request, see `ResultCache::admit`.
+pub(crate) struct CacheKey {
+    key: String,
+    collections: Vec<String>,
+    /// The generation of `collections` before the request was sent.
+    generation: u64,
+}
+
+#[derive(Deserialize)]
+struct Complete {
+    #[serde(default)]
+    error: bool,
+    #[serde(rename = "hasMore", default)]
+    has_more: bool,
+}
+
+impl Default for ResultCache {
+    fn default() -> Self {
+        Self {
+            ttl: Duration::from_secs(10),
+            max_entries: 1024,
+            entries: Mutex::new(HashMap::new()),
+            generations: Mutex::new(Generations::default()),
+            hits: AtomicU64::new(0),
+            misses: AtomicU64::new(0),
+        }
+    }
+}
+
+impl ResultCache {
+    #[must_use]
+    /// How long results are answered from the cache, 10 seconds by default.
+    pub fn ttl(self, ttl: Duration) -> Self {
+        Self { ttl, ..self }
+    }
+
+    #[must_use]
+    /// The number of results cached, 1024 by default. The oldest result is evicted when full.
+    pub fn max_entries(self, max_entries: usize) -> Self {
+        Self { max_entries, ..self }
+    }
+
+    /// # Panics
+    ///
+    /// Panics if a thread panicked while updating the cache.
+    #[must_use]
+    pub fn stats(&self) -> CacheStats {
+        CacheStats {
+            hits: self.hits.load(Ordering::Relaxed),
+            misses: self.misses.load(Ordering::Relaxed),
+            entries: self.entries.lock().unwrap().len(),
+        }
+    }
+
+    /// Drops the cached results of the queries binding `collection`, e.g. after another client
+    /// wrote to it.
+    /// # Panics
+    ///
+    /// Panics if a thread panicked while updating the cache.
+    pub fn invalidate(&self, collection: &str) {
+        let mut generations = self.generations.lock().unwrap();
+        *generations.collections.entry(collection.to_owned()).or_default() += 1;
+        let mut entries = self.entries.lock().unwrap();
+        entries.retain(|_, entry| !entry.collections.iter().any(|bound| bound == collection));
+    }
+
+    /// Drops all cached results.
+    /// # Panics
+    ///
+    /// Panics if a thread panicked while updating the cache.
+    pub fn clear(&self) {
+        let mut generations = self.generations.lock().unwrap();
+        generations.all += 1;
+        self.entries.lock().unwrap().clear();
+    }
+
+    /// The key of `request` if it is a read-only query binding its collections, or the
+    /// collections written by other queries, imports and the document API, whose results are
+    /// invalidated.
+    /// The written collections are invalidated again with `written` when the write finished,
+    /// so that results read while it was running are not cached.
+    pub(crate) fn admit(
+        &self,
+        request: &TransportRequest,
+        query: Option<&ArangoQuery>,
+    ) -> Option<Admission> {
+        let Some(query) = query else {
+            let import = request.method == Method::Post && request.url.ends_with("/_api/import");
+            let collection = request.query.iter().find(|(name, _)| import && name == "collection");
+            let document = request.url.rsplit_once("/_api/document/");
+            let written = match (collection, document) {
+                (Some((_, collection)), _) => collection.clone(),
+                (None, Some((_, path))) if request.method != Method::Get => {
+                    path.split('/').next().unwrap_or_default().to_owned()
+                }
+                _ => return None,
+            };
+            let written = Some(vec![written]);
+            self.written(written.as_deref());
+            return Some(Admission::Write(written));
+        };
+        let collections: Vec<String> = query
+            .bind_vars
+            .iter()
+            .filter(|(name, _)| name.starts_with('@'))
+            .filter_map(|(_, value)| value.as_str().map(str::to_owned))
+            .collect();
+        if is_read_only(&query.query) {
+            if collections.is_empty() {
+                return None;
+            }
+            let body = String::from_utf8_lossy(&request.body);
+            let key = format!("{}\n{body}", request.url);
+            let generation = self.generations.lock().unwrap().of(&collections);
+            return Some(Admission::Read(CacheKey { key, collections, generation }));
+        }
+        let written = Some(collections).filter(|collections| !collections.is_empty());
+        self.written(written.as_deref());
+        Some(Admission::Write(written))
+    }
+
+    /// Invalidates the results of `collections`, all results if `None`.
+    pub(crate) fn written(&self, collections: Option<&[String]>) {
+        let Some(collections) = collections else {
+            return self.clear();
+        };
+        for collection in collections {
+            self.invalidate(collection);
+        }
+    }
+
+    /// The cached response of `key`, if not expired.
+    pub(crate) fn get(&self, key: &CacheKey) -> Option<TransportResponse> {
+        let mut entries = self.entries.lock().unwrap();
+        let fresh = entries.get(&key.key).filter(|entry| entry.stored.elapsed() < self.ttl);
+        let response = fresh.map(|entry| entry.response.clone());
+        if response.is_some() {
+            self.hits.fetch_add(1, Ordering::Relaxed);
+        } else {
+            entries.remove(&key.key);
+            self.misses.fetch_add(1, Ordering::Relaxed);
+        }
+        response
+    }
+
+    /// Caches `response` of `key` if it is a complete result,
+    /// and its collections were not invalidated since the request was sent.
+    pub(crate) fn insert(&self, key: CacheKey, response: &TransportResponse) {
+        let complete = response.status < 300
+            && serde_json::from_slice::<Complete>(&response.body)
+                .is_ok_and(|body| !body.error && !body.has_more);
+        if !complete || self.max_entries == 0 {
+            return;
+        }
+        // held until inserted, so that no invalidation falls in between
+        let generations = self.generations.lock().unwrap();
+        if generations.of(&key.collections) != key.generation {
+            return;
+        }
+        let mut entries = self.entries.lock().unwrap();
+        if entries.len() >= self.max_entries && !entries.contains_key(&key.key) {
+            entries.retain(|_, entry| entry.stored.elapsed() < self.ttl);
+            let oldest = entries.iter().min_by_key(|(_, entry)| entry.stored);
+            if let Some(oldest) = oldest.filter(|_| entries.len() >= self.max_entries) {
+                let oldest = oldest.0.clone();
+                entries.remove(&oldest);
+            }
+        }
+        let entry = Entry {
+            response: response.clone(),
+            collections: key.collections,
+            stored: Instant::now(),
+        };
+        entries.insert(key.key, entry);
+    }
+}
@@ -0,0 +1,129 @@
+use crate::arango_api::ArangoQuery;
+use crate::error::ArangoError;
+use crate::transport::{Method, TransportRequest, TransportResponse};
+use rand::Rng;
+use serde::Deserialize;
+use std::time::Duration;
+
+/// Retries of transient failures, like 503s and connection resets during a cluster failover,
+/// see `ArangoConnection::with_retry`.
+///
+/// Only idempotent requests are retried: read-only queries, `GET` requests and queries
+/// opted in with `ArangoQuery::retry`.
+/// ```ignore
+/// let conn = conn.with_retry(RetryPolicy::default().max_attempts(5));
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct RetryPolicy {
+    pub(crate) max_attempts: u32,
+    pub(crate) initial_backoff: Duration,
+    pub(crate) max_backoff: Duration,
+    pub(crate) jitter: bool,
+    pub(crate) statuses: Vec<u16>,
+    pub(crate) error_nums: Vec<u64>,
+    pub(crate) transport_errors: bool,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_attempts: 3,
+            initial_backoff: Duration::from_millis(100),
+            max_backoff: Duration::from_secs(5),
+            jitter: true,
+            statuses: vec![503],
+            // lock timeouts
+            error_nums: vec![18, 1004],
+            transport_errors: true,
+        }
+    }
+}
+
+impl RetryPolicy {
+    #[must_use]
+    /// The number of attempts including the first one, at least 1.
+    pub fn max_attempts(self, max_attempts: u32) -> Self {
+        Self { max_attempts: max_attempts.max(1), ..self }
+    }
+
+    #[must_use]
+    /// The backoff is `initial_backoff` doubled after every attempt, at most `max_backoff`.
+    pub fn backoff(self, initial_backoff: Duration, max_backoff: Duration) -> Self {
+        Self { initial_backoff, max_backoff, ..self }
+    }
+
+    #[must_use]
+    /// Waits a random duration up to the backoff instead of the backoff itself,
+    /// so that clients failing together do not retry together.
+    pub fn jitter(self, jitter: bool) -> Self {
+        Self { jitter, ..self }
+    }
+
+    #[must_use]
+    /// The HTTP statuses retried, 503 by default.
+    pub fn statuses(self, statuses: &[u16]) -> Self {
+        Self { statuses: statuses.to_vec(), ..self }
+    }
+
+    #[must_use]
+    /// The `errorNum`s of responses retried, the lock timeouts 18 and 1004 by default.
+    pub fn error_nums(self, error_nums: &[u64]) -> Self {
+        Self { error_nums: error_nums.to_vec(), ..self }
+    }
+
+    #[must_use]
+    /// Retries failures of the transport, like refused or reset connections, the default.
+    pub fn transport_errors(self, transport_errors: bool) -> Self {
+        Self { transport_errors, ..self }
+    }
+
+    /// Whether `request` may be sent again.
+    pub(crate) fn is_idempotent(request: &TransportRequest, query: Option<&ArangoQuery>) -> bool {
+        match query {
+            Some(query) => query.retry.unwrap_or_else(|| is_read_only(&query.query)),
+            None => request.method == Method::Get,
+        }
+    }
+
+    /// Whether `response` is a transient failure.
+    pub(crate) fn is_transient(&self, response: &Result<TransportResponse, ArangoError>) -> bool {
+        #[derive(Deserialize)]
+        struct ErrorNum {
+            #[serde(rename = "errorNum")]
+            error_num: u64,
+        }
+
+        match response {
+            Ok(response) if response.status < 400 => false,
+            Ok(response) => {
+                self.statuses.contains(&response.status)
+                    || serde_json::from_slice::<ErrorNum>(&response.body)
+                        .is_ok_and(|body| self.error_nums.contains(&body.error_num))
+            }
+            Err(ArangoError::Connect(_) | ArangoError::Transport(_)) => self.transport_errors,
+            Err(_) => false,
+        }
+    }
+
+    /// The wait before the attempt after `attempt`, counted from 1.
+    pub(crate) fn backoff_after(&self, attempt: u32) -> Duration {
+        let factor = 2_u32.saturating_pow(attempt.saturating_sub(1));
+        let backoff = self.initial_backoff.checked_mul(factor).unwrap_or(self.max_backoff);
+        let backoff = backoff.min(self.max_backoff);
+        if self.jitter {
+            backoff.mul_f64(rand::thread_rng().gen_range(0.0..=1.0))
+        } else {
+            backoff
+        }
+    }
+}
+
+/// Whether an AQL query only reads, it has none of the data modification keywords.
+/// Keywords in string literals make a query count as writing, which is not retried by default.
+pub(crate) fn is_read_only(query: &str) -> bool {
+    const WRITES: [&str; 5] = ["INSERT", "UPDATE", "REPLACE", "REMOVE", "UPSERT"];
+
+    !query
+        .split(|c: char| !c.is_alphanumeric() && c != '_')
+        .any(|word| WRITES.iter().any(|write| write.eq_ignore_ascii_case(word)))
+}
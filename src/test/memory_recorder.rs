@@ -0,0 +1,51 @@
+use crate::metrics::MetricsRecorder;
+use std::collections::BTreeMap;
+use std::sync::Mutex;
+
+type Key = (&'static str, Vec<(&'static str, String)>);
+
+fn key(name: &'static str, labels: &[(&'static str, &str)]) -> Key {
+    (name, labels.iter().map(|(label, value)| (*label, (*value).to_owned())).collect())
+}
+
+/// A `MetricsRecorder` keeping every observation in memory, for assertions.
+#[derive(Default)]
+pub struct MemoryRecorder {
+    histograms: Mutex<BTreeMap<Key, Vec<f64>>>,
+    counters: Mutex<BTreeMap<Key, u64>>,
+}
+
+impl MemoryRecorder {
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The values observed for the histogram `name` with exactly `labels`.
+    /// # Panics
+    ///
+    /// Panics if a recording thread panicked.
+    #[must_use]
+    pub fn histogram(&self, name: &'static str, labels: &[(&'static str, &str)]) -> Vec<f64> {
+        self.histograms.lock().unwrap().get(&key(name, labels)).cloned().unwrap_or_default()
+    }
+
+    /// The value of the counter `name` with exactly `labels`.
+    /// # Panics
+    ///
+    /// Panics if a recording thread panicked.
+    #[must_use]
+    pub fn counter(&self, name: &'static str, labels: &[(&'static str, &str)]) -> u64 {
+        self.counters.lock().unwrap().get(&key(name, labels)).copied().unwrap_or_default()
+    }
+}
+
+impl MetricsRecorder for MemoryRecorder {
+    fn observe(&self, name: &'static str, labels: &[(&'static str, &str)], value: f64) {
+        self.histograms.lock().unwrap().entry(key(name, labels)).or_default().push(value);
+    }
+
+    fn increment(&self, name: &'static str, labels: &[(&'static str, &str)], value: u64) {
+        *self.counters.lock().unwrap().entry(key(name, labels)).or_default() += value;
+    }
+}
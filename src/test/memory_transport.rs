@@ -0,0 +1,67 @@
+use crate::error::ArangoError;
+use crate::transport::{Transport, TransportRequest, TransportResponse};
+use futures_util::future::{self, BoxFuture, FutureExt};
+use std::sync::Mutex;
+use std::time::Duration;
+
+type Handler =
+    Box<dyn Fn(&TransportRequest) -> Result<TransportResponse, ArangoError> + Send + Sync>;
+
+/// A `Transport` which answers every request with `handler`, without a server,
+/// and records the requests for assertions.
+/// ```ignore
+/// let transport = Arc::new(MemoryTransport::new(|_| TransportResponse::new(201, r#"{"result":[]}"#)));
+/// let conn = ArangoConnection::with_transport(host, db_name, transport.clone(), Context::default());
+/// query.try_exec::<Value>(&conn).await?;
+/// assert_eq!(1, transport.requests().len());
+/// ```
+#[allow(clippy::module_name_repetitions)]
+pub struct MemoryTransport {
+    handler: Handler,
+    requests: Mutex<Vec<TransportRequest>>,
+}
+
+impl MemoryTransport {
+    #[must_use]
+    pub fn new<F>(handler: F) -> Self
+    where
+        F: Fn(&TransportRequest) -> TransportResponse + Send + Sync + 'static,
+    {
+        Self::fallible(move |request| Ok(handler(request)))
+    }
+
+    /// Same as `new`, with a `handler` which may fail like a transport,
+    /// e.g. with `ArangoError::Connect` for an unreachable host.
+    #[must_use]
+    pub fn fallible<F>(handler: F) -> Self
+    where
+        F: Fn(&TransportRequest) -> Result<TransportResponse, ArangoError> + Send + Sync + 'static,
+    {
+        Self { handler: Box::new(handler), requests: Mutex::new(vec![]) }
+    }
+
+    /// The requests sent so far, in order.
+    /// # Panics
+    ///
+    /// Panics if a handler panicked while the requests were locked.
+    #[must_use]
+    pub fn requests(&self) -> Vec<TransportRequest> {
+        self.requests.lock().unwrap().clone()
+    }
+}
+
+impl Transport for MemoryTransport {
+    fn send(
+        &self,
+        request: TransportRequest,
+    ) -> BoxFuture<'static, Result<TransportResponse, ArangoError>> {
+        let response = (self.handler)(&request);
+        self.requests.lock().unwrap().push(request);
+        future::ready(response).boxed()
+    }
+
+    /// Returns at once, backoffs do not slow down tests.
+    fn sleep(&self, _duration: Duration) -> BoxFuture<'static, ()> {
+        future::ready(()).boxed()
+    }
+}
@@ -2,9 +2,13 @@ use crate::arango_response::ResponseExtra;
 use serde::{Deserialize, Serialize};
 
 mod arango_mock;
+mod memory_recorder;
+mod memory_transport;
 
 #[allow(unused_imports)] // used in test
 pub use arango_mock::*;
+pub use memory_recorder::*;
+pub use memory_transport::*;
 
 #[allow(clippy::module_name_repetitions)]
 #[derive(Debug, PartialEq, Serialize, Deserialize, Default)]
@@ -0,0 +1,185 @@
+use crate::error::ArangoError;
+use crate::queries::ArangoStatus;
+use futures_util::future::BoxFuture;
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use std::time::Duration;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Method {
+    Get,
+    Post,
+    Put,
+    Patch,
+    Delete,
+}
+
+/// An HTTP request to the database, independent of the HTTP client.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TransportRequest {
+    pub method: Method,
+    pub url: String,
+    /// Query string parameters, not yet encoded into `url`.
+    pub query: Vec<(String, String)>,
+    pub headers: Vec<(String, String)>,
+    pub body: Vec<u8>,
+    /// Time until the transport gives up with `ArangoError::Timeout`, none by default.
+    pub timeout: Option<Duration>,
+}
+
+impl TransportRequest {
+    #[must_use]
+    pub fn new(method: Method, url: String) -> Self {
+        Self { method, url, query: vec![], headers: vec![], body: vec![], timeout: None }
+    }
+
+    #[must_use]
+    pub fn header(mut self, name: &str, value: &str) -> Self {
+        self.headers.push((name.to_owned(), value.to_owned()));
+        self
+    }
+
+    #[must_use]
+    pub fn query(mut self, params: &[(&str, &str)]) -> Self {
+        self.query
+            .extend(params.iter().map(|(name, value)| ((*name).to_owned(), (*value).to_owned())));
+        self
+    }
+
+    #[must_use]
+    pub fn body(self, body: Vec<u8>) -> Self {
+        Self { body, ..self }
+    }
+
+    #[must_use]
+    pub fn timeout(self, timeout: Duration) -> Self {
+        Self { timeout: Some(timeout), ..self }
+    }
+
+    #[must_use]
+    /// Sets the body to `value` serialized as JSON.
+    /// # Panics
+    ///
+    /// Panics if `value` fails to serialize, e.g. a map with non-string keys.
+    pub fn json<T: Serialize + ?Sized>(self, value: &T) -> Self {
+        self.header("content-type", "application/json").body(serde_json::to_vec(value).unwrap())
+    }
+}
+
+/// An HTTP response of the database, independent of the HTTP client.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct TransportResponse {
+    pub status: u16,
+    pub headers: Vec<(String, String)>,
+    pub body: Vec<u8>,
+}
+
+impl TransportResponse {
+    #[must_use]
+    pub fn new(status: u16, body: impl Into<Vec<u8>>) -> Self {
+        Self { status, headers: vec![], body: body.into() }
+    }
+
+    #[must_use]
+    /// The first value of the header `name`, ignoring its case.
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.headers
+            .iter()
+            .find(|(key, _)| key.eq_ignore_ascii_case(name))
+            .map(|(_, value)| value.as_str())
+    }
+
+    /// Deserializes the body.
+    /// # Errors
+    ///
+    /// Returns `ArangoError::Json` if the body is not a JSON `T`.
+    pub fn json<T: DeserializeOwned>(&self) -> Result<T, ArangoError> {
+        Ok(serde_json::from_slice(&self.body)?)
+    }
+
+    /// Deserializes the body of a successful response.
+    /// # Errors
+    ///
+    /// Returns `ArangoError::Server` with the error of the body if the status is an error,
+    /// `ArangoError::Json` if the body is not a JSON `T`.
+    pub fn result<T: DeserializeOwned>(&self) -> Result<T, ArangoError> {
+        if self.status < 400 {
+            return self.json();
+        }
+        let status = self.json().unwrap_or_else(|_| ArangoStatus {
+            error: true,
+            code: self.status,
+            error_message: String::from_utf8_lossy(&self.body).into_owned(),
+            error_num: 0,
+        });
+        Err(ArangoError::Server(status))
+    }
+}
+
+/// Sends the requests of an `ArangoConnection`.
+///
+/// Implemented for `reqwest::Client` with the default `reqwest` feature.
+/// Implement it to use another HTTP client or runtime, or to wrap a transport with middleware,
+/// with `sleep` as well without the `tokio` feature.
+/// `crate::test::MemoryTransport` answers requests in memory, without a server.
+pub trait Transport: Send + Sync {
+    fn send(
+        &self,
+        request: TransportRequest,
+    ) -> BoxFuture<'static, Result<TransportResponse, ArangoError>>;
+
+    /// Waits `duration` before a retry, on the runtime of the transport.
+    /// Uses the `tokio` timer with the default `tokio` feature.
+    #[cfg(feature = "tokio")]
+    fn sleep(&self, duration: Duration) -> BoxFuture<'static, ()> {
+        Box::pin(tokio::time::delay_for(duration))
+    }
+
+    /// Waits `duration` before a retry, on the runtime of the transport.
+    /// Required without the `tokio` feature, a future ready at once would turn
+    /// the backoffs of `RetryPolicy` into a busy loop.
+    #[cfg(not(feature = "tokio"))]
+    fn sleep(&self, duration: Duration) -> BoxFuture<'static, ()>;
+}
+
+#[cfg(feature = "reqwest")]
+impl Transport for reqwest::Client {
+    fn send(
+        &self,
+        request: TransportRequest,
+    ) -> BoxFuture<'static, Result<TransportResponse, ArangoError>> {
+        use futures_util::future::FutureExt;
+
+        let mut builder = match request.method {
+            Method::Get => self.get(&request.url),
+            Method::Post => self.post(&request.url),
+            Method::Put => self.put(&request.url),
+            Method::Patch => self.patch(&request.url),
+            Method::Delete => self.delete(&request.url),
+        };
+        if !request.query.is_empty() {
+            builder = builder.query(&request.query);
+        }
+        for (name, value) in &request.headers {
+            builder = builder.header(name.as_str(), value.as_str());
+        }
+        if let Some(timeout) = request.timeout {
+            builder = builder.timeout(timeout);
+        }
+        let sent = builder.body(request.body).send();
+        async move {
+            let response = sent.await?;
+            let status = response.status().as_u16();
+            let headers = response
+                .headers()
+                .iter()
+                .filter_map(|(name, value)| {
+                    Some((name.as_str().to_owned(), value.to_str().ok()?.to_owned()))
+                })
+                .collect();
+            let body = response.bytes().await?.to_vec();
+            Ok(TransportResponse { status, headers, body })
+        }
+        .boxed()
+    }
+}
@@ -1,3 +1,4 @@
+#![cfg(feature = "reqwest")]
 #[cfg(test)]
 
 mod tests {
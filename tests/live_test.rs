@@ -1,3 +1,4 @@
+#![cfg(feature = "reqwest")]
 #[cfg(feature = "actors")]
 use actix::{Actor, System};
 #[cfg(feature = "actors")]
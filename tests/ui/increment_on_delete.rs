@@ -0,0 +1,17 @@
+use arangoq::*;
+use serde::Serialize;
+
+#[derive(ArangoBuilder, Serialize)]
+pub struct Person {
+    name: String,
+    age: u8,
+}
+
+fn main() {
+    let _query = Person::query_builder("People")
+        .delete()
+        .filter()
+        .age_eq(&1)
+        .age_increment(&1)
+        .build();
+}
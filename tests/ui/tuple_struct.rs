@@ -0,0 +1,7 @@
+use arangoq::*;
+use serde::Serialize;
+
+#[derive(ArangoBuilder, Serialize)]
+pub struct Person(String, u8);
+
+fn main() {}
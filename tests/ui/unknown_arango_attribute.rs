@@ -0,0 +1,16 @@
+use arangoq::*;
+use serde::Serialize;
+
+#[derive(ArangoBuilder, Serialize)]
+pub struct Address {
+    city: String,
+}
+
+#[derive(ArangoBuilder, Serialize)]
+pub struct Person {
+    name: String,
+    #[arango(nestde)]
+    address: Address,
+}
+
+fn main() {}